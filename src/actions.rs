@@ -0,0 +1,128 @@
+// What the picker can do with a task besides launching an agent on it
+
+use crate::config::Config;
+use crate::files::{self, relative};
+use crate::ids;
+use crate::new_task::{cancelled, frontmatter_value, slugify};
+use crate::task::{self, Task};
+use inquire::Text;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Open `path` in $VISUAL or $EDITOR, falling back to vi
+pub fn edit(root: &Path, path: &Path) -> io::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+
+    // Editors are often set with flags, like "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .current_dir(root)
+        .status()
+        .map_err(|e| io::Error::new(e.kind(), format!("could not run {}: {}", program, e)))?;
+
+    if !status.success() {
+        eprintln!("Warning: {} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+// Move a task under `tasks.archive_dir`, keeping its folder below the tasks
+// directory so archived tasks stay grouped the same way
+pub fn archive(root: &Path, config: &Config, path: &Path) -> io::Result<PathBuf> {
+    let tasks_dir = config.tasks_dir(root);
+    let below = path.strip_prefix(&tasks_dir).unwrap_or(path);
+    let target = config.archive_dir(root).join(below);
+
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", relative(root, &target)),
+        ));
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(path, &target)?;
+    files::remove_empty_parents(&tasks_dir, path);
+
+    println!(
+        "📦 Archived {} to {}",
+        relative(root, path),
+        relative(root, &target)
+    );
+    Ok(target)
+}
+
+// Ask for a new title, rewrite it in the frontmatter and heading, and rename
+// the file to match. Links to the old file name in other tasks follow.
+pub fn rename(root: &Path, config: &Config, task: &Task) -> io::Result<PathBuf> {
+    let current = task.title.clone().unwrap_or_default();
+    let title = Text::new("New title:")
+        .with_initial_value(&current)
+        .with_help_message("The file is renamed to match")
+        .prompt()
+        .map_err(cancelled)?;
+    let title = title.trim();
+    if title.is_empty() || title == current {
+        println!("Title unchanged.");
+        return Ok(task.path.clone());
+    }
+
+    let id = task.id();
+    let today = chrono::Local::now().date_naive();
+    let content = fs::read_to_string(&task.path)?;
+    let content = task::set_field(&content, "title", &frontmatter_value(title));
+    let content = task::set_field(&content, "updated", &format!("\"{}\"", today));
+    let content = rename_heading(&content, &id, title);
+
+    let new_path = task
+        .path
+        .with_file_name(format!("{}-{}.md", id, slugify(title)));
+    if new_path != task.path && new_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", relative(root, &new_path)),
+        ));
+    }
+
+    fs::write(&task.path, content)?;
+    if new_path != task.path {
+        fs::rename(&task.path, &new_path)?;
+    }
+    println!("✓ {}: {}  ({})", id, title, relative(root, &new_path));
+    ids::rename_links(root, &config.tasks_dir(root), &task.path, &new_path, false)?;
+    Ok(new_path)
+}
+
+// Replace the title in the "# TASK_001: Title" heading
+fn rename_heading(content: &str, id: &str, title: &str) -> String {
+    let prefix = format!("# {}:", id);
+    let mut renamed = false;
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            if !renamed && line.starts_with(&prefix) {
+                renamed = true;
+                format!("{} {}", prefix, title)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
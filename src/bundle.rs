@@ -0,0 +1,470 @@
+// Several tasks bundled into one prompt for folder and multi-task launches.
+// Marked entries keep the order they were marked in. The tasks of a folder
+// are ordered so each comes after the tasks it `depends_on`, then by status
+// (finished work first, as background) and id. When the estimated
+// size exceeds `run.token_budget`, or the final prompt with its rules would
+// be longer than an agent's command line can take, finished tasks are cut to
+// their Summary and Lessons Learned, then left out, then the last active
+// tasks are left out; the agent is told which files were left out.
+
+use crate::files::relative;
+use crate::ids;
+use crate::prompt;
+use crate::status;
+use crate::task::{self, Task};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Position of each status in the bundle; unknown statuses go last
+const STATUS_ORDER: &[&str] = &[
+    "done",
+    "in-review",
+    "in-progress",
+    "ready",
+    "draft",
+    "abandoned",
+];
+
+// Rough characters per token for English markdown
+const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Form {
+    Full,
+    // Summary and Lessons Learned only
+    Summary,
+    Dropped,
+}
+
+struct Entry {
+    path: PathBuf,
+    id: String,
+    name: String,
+    status: Option<&'static str>,
+    depends_on: Vec<String>,
+    content: String,
+    summary: String,
+    form: Form,
+}
+
+pub struct Bundle {
+    pub content: String,
+    pub tokens: usize,
+    // Tasks given, counting each file once
+    pub tasks: usize,
+    // Tasks in the bundle, without the ones left out
+    pub included: usize,
+    // One line per task that was cut or left out
+    pub report: Vec<String>,
+}
+
+// `groups` holds one list per picked entry: a single task, or the tasks of
+// a folder. A task already in an earlier group is left out of later ones.
+// `prompt_bytes` gives the length of the prompt that will be sent for a
+// bundled content, template and rules included.
+pub fn bundle(
+    root: &Path,
+    groups: &[Vec<PathBuf>],
+    budget: usize,
+    prompt_bytes: &dyn Fn(&str) -> usize,
+) -> io::Result<Bundle> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for group in groups {
+        let mut grouped = Vec::new();
+        for path in group {
+            if !entries
+                .iter()
+                .chain(&grouped)
+                .any(|entry| entry.path == *path)
+            {
+                grouped.push(entry(path)?);
+            }
+        }
+        entries.extend(order(grouped));
+    }
+
+    fit(root, &mut entries, budget, prompt_bytes);
+    let report = entries
+        .iter()
+        .filter_map(|entry| match entry.form {
+            Form::Full => None,
+            Form::Summary => Some(format!(
+                "✂ {}: Summary and Lessons Learned only (~{} → ~{} tokens)",
+                entry.name,
+                approx(estimate(&entry.content)),
+                approx(estimate(&entry.summary))
+            )),
+            Form::Dropped => Some(format!(
+                "✗ {}: left out (~{} tokens)",
+                entry.name,
+                approx(estimate(&entry.content))
+            )),
+        })
+        .collect();
+
+    let content = render(root, &entries);
+    Ok(Bundle {
+        tokens: estimate(&content),
+        tasks: entries.len(),
+        included: entries
+            .iter()
+            .filter(|entry| entry.form != Form::Dropped)
+            .count(),
+        content,
+        report,
+    })
+}
+
+// Estimated tokens in `text`
+pub fn estimate(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+// 840 or 12.3k
+pub fn approx(tokens: usize) -> String {
+    if tokens < 1000 {
+        tokens.to_string()
+    } else {
+        format!("{:.1}k", tokens as f64 / 1000.0)
+    }
+}
+
+fn entry(path: &Path) -> io::Result<Entry> {
+    let content = fs::read_to_string(path)?;
+    let entry = match Task::parse(path, &content) {
+        Ok(task) => Entry {
+            id: task.id(),
+            name: match &task.title {
+                Some(title) => format!("{}: {}", task.id(), title),
+                None => task.id(),
+            },
+            status: status::current(&task).ok(),
+            depends_on: task.depends_on(),
+            summary: summary(&task),
+            path: path.to_path_buf(),
+            content,
+            form: Form::Full,
+        },
+        Err(_) => Entry {
+            id: task::file_id(path),
+            name: task::file_id(path),
+            status: None,
+            depends_on: Vec::new(),
+            summary: String::new(),
+            path: path.to_path_buf(),
+            content,
+            form: Form::Full,
+        },
+    };
+    Ok(entry)
+}
+
+// The Summary and Lessons Learned sections, all a finished task needs to
+// pass on
+fn summary(task: &Task) -> String {
+    ["Summary", "Lessons Learned"]
+        .iter()
+        .filter_map(|title| {
+            let section = task.section(title)?;
+            let text = task::strip_comments(&section.content);
+            let text = text.trim();
+            (!text.is_empty()).then(|| format!("## {}\n\n{}", title, text))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// The tasks of a folder: dependencies first, then by status and id. A cycle
+// is broken at the task that sorts first.
+fn order(mut entries: Vec<Entry>) -> Vec<Entry> {
+    entries.sort_by_key(sort_key);
+
+    let mut ordered: Vec<Entry> = Vec::new();
+    while !entries.is_empty() {
+        let waiting = |entry: &Entry| {
+            entry.depends_on.iter().any(|id| {
+                entries
+                    .iter()
+                    .any(|other| other.id.eq_ignore_ascii_case(id) && other.path != entry.path)
+            })
+        };
+        let next = entries
+            .iter()
+            .position(|entry| !waiting(entry))
+            .unwrap_or(0);
+        ordered.push(entries.remove(next));
+    }
+    ordered
+}
+
+fn sort_key(entry: &Entry) -> (usize, u64, String) {
+    let status = entry
+        .status
+        .and_then(|status| STATUS_ORDER.iter().position(|s| *s == status))
+        .unwrap_or(STATUS_ORDER.len());
+    // TASK_9 before TASK_10
+    let number = ids::task_number(&entry.id).unwrap_or(u64::MAX);
+    (status, number, entry.id.clone())
+}
+
+fn finished(entry: &Entry) -> bool {
+    matches!(entry.status, Some("done" | "abandoned"))
+}
+
+// Cut and leave out tasks until the bundle fits `budget` (0: no limit) and
+// the prompt around it fits `prompt::MAX_BYTES`
+fn fit(root: &Path, entries: &mut [Entry], budget: usize, prompt_bytes: &dyn Fn(&str) -> usize) {
+    let over = |entries: &[Entry]| {
+        let content = render(root, entries);
+        (budget > 0 && estimate(&content) > budget) || prompt_bytes(&content) > prompt::MAX_BYTES
+    };
+
+    // Finished tasks keep their Summary and Lessons Learned
+    for index in 0..entries.len() {
+        if !over(entries) {
+            return;
+        }
+        let entry = &mut entries[index];
+        if finished(entry) && estimate(&entry.summary) < estimate(&entry.content) {
+            entry.form = Form::Summary;
+        }
+    }
+
+    // Then they go entirely, then active tasks from the end. The first active
+    // task stays, or the first task when all of them are finished.
+    let kept = (0..entries.len())
+        .find(|index| !finished(&entries[*index]))
+        .unwrap_or(0);
+    let candidates: Vec<usize> = (0..entries.len())
+        .filter(|index| finished(&entries[*index]))
+        .chain(
+            (0..entries.len())
+                .rev()
+                .filter(|index| !finished(&entries[*index])),
+        )
+        .filter(|index| *index != kept)
+        .collect();
+    for index in candidates {
+        if !over(entries) {
+            return;
+        }
+        entries[index].form = Form::Dropped;
+    }
+}
+
+// A numbered table of contents, then each task under its own header
+fn render(root: &Path, entries: &[Entry]) -> String {
+    let included: Vec<&Entry> = entries
+        .iter()
+        .filter(|entry| entry.form != Form::Dropped)
+        .collect();
+    let dropped: Vec<&Entry> = entries
+        .iter()
+        .filter(|entry| entry.form == Form::Dropped)
+        .collect();
+
+    let mut output = format!("# Tasks ({})\n\n", included.len());
+    for (index, entry) in included.iter().enumerate() {
+        let note = match entry.form {
+            Form::Summary => ", Summary and Lessons Learned only",
+            _ => "",
+        };
+        output.push_str(&format!(
+            "{}. {} ({}{})\n",
+            index + 1,
+            entry.name,
+            relative(root, &entry.path),
+            note
+        ));
+    }
+    if !dropped.is_empty() {
+        output.push_str("\nLeft out to fit the context budget; read them if you need them:\n");
+        for entry in &dropped {
+            output.push_str(&format!(
+                "- {} ({})\n",
+                entry.name,
+                relative(root, &entry.path)
+            ));
+        }
+    }
+
+    for (index, entry) in included.iter().enumerate() {
+        let content = match entry.form {
+            Form::Summary => &entry.summary,
+            _ => &entry.content,
+        };
+        output.push_str(&format!(
+            "\n---\n\n## Task {} of {}: {}\n\nFile: {}\n\n{}\n",
+            index + 1,
+            included.len(),
+            entry.name,
+            relative(root, &entry.path),
+            content.trim_end()
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(dir: &Path, id: &str, status: &str, depends_on: &str) -> PathBuf {
+        let path = dir.join(format!("{}.md", id));
+        let content = format!(
+            "---\ntask_id: {}\ntitle: Task\nstatus: {}\ndepends_on: [{}]\n---\n\n# {}\n",
+            id, status, depends_on, id
+        );
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn files(bundle: &Bundle) -> Vec<&str> {
+        bundle
+            .content
+            .lines()
+            .filter_map(|line| line.strip_prefix("File: "))
+            .collect()
+    }
+
+    #[test]
+    fn marked_tasks_keep_the_order_they_were_picked() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let first = task(root, "TASK_003", "ready", "");
+        let second = task(root, "TASK_001", "done", "");
+        let third = task(root, "TASK_002", "ready", "TASK_003");
+
+        let groups = [vec![first], vec![second], vec![third]];
+        let bundle = bundle(root, &groups, 0, &str::len).unwrap();
+        assert_eq!(
+            files(&bundle),
+            ["TASK_003.md", "TASK_001.md", "TASK_002.md"]
+        );
+    }
+
+    #[test]
+    fn a_folder_is_ordered_by_dependencies_then_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let folder = vec![
+            task(root, "TASK_001", "ready", "TASK_002"),
+            task(root, "TASK_002", "ready", ""),
+            task(root, "TASK_003", "done", ""),
+        ];
+        let picked = task(root, "TASK_004", "draft", "");
+
+        // A task both marked and in a folder is bundled once
+        let groups = [vec![picked.clone()], folder, vec![picked]];
+        let bundle = bundle(root, &groups, 0, &str::len).unwrap();
+        assert_eq!(bundle.tasks, 4);
+        assert_eq!(
+            files(&bundle),
+            ["TASK_004.md", "TASK_003.md", "TASK_002.md", "TASK_001.md"]
+        );
+    }
+
+    // A task with a short Summary and `size` bytes of Goals
+    fn long_task(dir: &Path, id: &str, status: &str, size: usize) -> PathBuf {
+        let path = dir.join(format!("{}.md", id));
+        let content = format!(
+            "---\ntask_id: {}\ntitle: Task\nstatus: {}\n---\n\n## Summary\n\nShort.\n\n## Goals\n\n{}\n",
+            id,
+            status,
+            "x".repeat(size)
+        );
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn a_dependency_cycle_is_broken_at_the_task_that_sorts_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let folder = vec![
+            task(root, "TASK_001", "ready", "TASK_002"),
+            task(root, "TASK_002", "ready", "TASK_001"),
+            task(root, "TASK_003", "ready", ""),
+            task(root, "TASK_004", "ready", "TASK_003"),
+        ];
+
+        let bundle = bundle(root, &[folder], 0, &str::len).unwrap();
+        assert_eq!(
+            files(&bundle),
+            ["TASK_003.md", "TASK_004.md", "TASK_001.md", "TASK_002.md"]
+        );
+    }
+
+    #[test]
+    fn ties_go_by_status_then_task_number() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        // Depending on itself or on a task outside the folder holds nothing up
+        let folder = vec![
+            task(root, "TASK_10", "ready", ""),
+            task(root, "TASK_12", "ready", "TASK_12, TASK_999"),
+            task(root, "TASK_9", "ready", ""),
+            task(root, "TASK_11", "in-progress", ""),
+        ];
+
+        let bundle = bundle(root, &[folder], 0, &str::len).unwrap();
+        assert_eq!(
+            files(&bundle),
+            ["TASK_11.md", "TASK_9.md", "TASK_10.md", "TASK_12.md"]
+        );
+    }
+
+    #[test]
+    fn finished_tasks_are_cut_to_their_summary_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let folder = vec![
+            long_task(root, "TASK_001", "done", 4000),
+            long_task(root, "TASK_002", "ready", 4000),
+        ];
+
+        let bundle = bundle(root, &[folder], 1500, &str::len).unwrap();
+        assert_eq!(bundle.included, 2);
+        assert!(bundle.tokens <= 1500);
+        assert_eq!(bundle.report.len(), 1);
+        assert!(bundle.report[0].starts_with("✂ TASK_001: Task"));
+        assert!(bundle
+            .content
+            .contains("TASK_001: Task (TASK_001.md, Summary and Lessons Learned only)"));
+    }
+
+    #[test]
+    fn the_first_active_task_stays_whatever_the_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let folder = vec![
+            long_task(root, "TASK_001", "done", 4000),
+            long_task(root, "TASK_002", "ready", 4000),
+            long_task(root, "TASK_003", "ready", 4000),
+        ];
+
+        let bundle = bundle(root, &[folder], 100, &str::len).unwrap();
+        assert_eq!(bundle.included, 1);
+        assert_eq!(files(&bundle), ["TASK_002.md"]);
+        assert!(bundle
+            .content
+            .contains("Left out to fit the context budget; read them if you need them:\n- TASK_001: Task (TASK_001.md)\n- TASK_003: Task (TASK_003.md)\n"));
+    }
+
+    #[test]
+    fn the_whole_prompt_is_kept_under_the_argument_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let folder = vec![
+            long_task(root, "TASK_001", "ready", 4000),
+            long_task(root, "TASK_002", "ready", 4000),
+        ];
+
+        // Rules and template that leave room for one task only, with no budget
+        let prompt_bytes = |content: &str| content.len() + prompt::MAX_BYTES - 6000;
+        let bundle = bundle(root, &[folder], 0, &prompt_bytes).unwrap();
+        assert_eq!(files(&bundle), ["TASK_001.md"]);
+        assert!(prompt_bytes(&bundle.content) <= prompt::MAX_BYTES);
+    }
+}
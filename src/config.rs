@@ -0,0 +1,555 @@
+use crate::ids::{self, IdStrategy};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Item, Table, Value};
+
+// Project settings, committed with the repository
+const PROJECT_CONFIG: &str = ".context/config.toml";
+
+// Settings read by cdd. Values come from the built-in defaults, overridden by
+// ~/.config/cdd/config.toml, overridden by .context/config.toml.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub run: RunConfig,
+    pub picker: PickerConfig,
+    pub tasks: TasksConfig,
+    pub agents: AgentsConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunConfig {
+    pub prompt_prefix: String,
+    // Estimated tokens of task content in a folder or multi-task launch; 0
+    // means no limit. The prompt is kept under `prompt::MAX_BYTES` either way.
+    pub token_budget: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PickerConfig {
+    pub height: String,
+    pub preview_window: String,
+    // Empty means the built-in preview
+    pub preview_command: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TasksConfig {
+    pub dir: String,
+    pub id_strategy: String,
+    pub archive_dir: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentsConfig {
+    pub default: String,
+    pub installed: Vec<String>,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        RunConfig {
+            prompt_prefix: "I want to work on this task:".to_string(),
+            // Leaves most of the agent's context window for the work itself
+            token_budget: 24000,
+        }
+    }
+}
+
+impl Default for PickerConfig {
+    fn default() -> Self {
+        PickerConfig {
+            height: "50%".to_string(),
+            preview_window: "right:60%:wrap".to_string(),
+            preview_command: String::new(),
+        }
+    }
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        TasksConfig {
+            dir: ".context/tasks".to_string(),
+            id_strategy: "sequential".to_string(),
+            archive_dir: ".context/archive".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum KeyKind {
+    String,
+    StringList,
+    Integer,
+}
+
+struct KeySpec {
+    key: &'static str,
+    kind: KeyKind,
+    description: &'static str,
+}
+
+// Personal choices kept in the user config, so a committed project file
+// can't make them for everyone who clones the repository
+const USER_ONLY: &[&str] = &["agents.default"];
+
+// Every key `cdd config` knows about, in `list` order
+const KEYS: &[KeySpec] = &[
+    KeySpec {
+        key: "run.prompt_prefix",
+        kind: KeyKind::String,
+        description: "Text placed before the task content when no prompt template is found",
+    },
+    KeySpec {
+        key: "run.token_budget",
+        kind: KeyKind::Integer,
+        description: "Estimated tokens of task content when launching several tasks (0: no limit)",
+    },
+    KeySpec {
+        key: "picker.height",
+        kind: KeyKind::String,
+        description: "Height of the task picker, in lines or percent (e.g. \"50%\")",
+    },
+    KeySpec {
+        key: "picker.preview_window",
+        kind: KeyKind::String,
+        description: "skim preview window layout (e.g. \"right:60%:wrap\")",
+    },
+    KeySpec {
+        key: "picker.preview_command",
+        kind: KeyKind::String,
+        description:
+            "Shell command previewing a task, {} is the task path (empty: built-in preview)",
+    },
+    KeySpec {
+        key: "tasks.dir",
+        kind: KeyKind::String,
+        description: "Directory holding task files, relative to the project root",
+    },
+    KeySpec {
+        key: "tasks.id_strategy",
+        kind: KeyKind::String,
+        description: "How `cdd new` numbers tasks: sequential, per-project, timestamp or hash",
+    },
+    KeySpec {
+        key: "tasks.archive_dir",
+        kind: KeyKind::String,
+        description: "Where archived tasks are moved, relative to the project root",
+    },
+    KeySpec {
+        key: "agents.default",
+        kind: KeyKind::String,
+        description: "Agent launched by `cdd run` when several are installed (user config only)",
+    },
+    KeySpec {
+        key: "agents.installed",
+        kind: KeyKind::StringList,
+        description: "Profiles installed in this project (maintained by `cdd install`)",
+    },
+];
+
+pub fn project_config_path(root: &Path) -> PathBuf {
+    root.join(PROJECT_CONFIG)
+}
+
+// Per-user settings, kept outside any repository
+pub fn user_config_path() -> Option<PathBuf> {
+    Some(dirs::home_dir()?.join(".config/cdd/config.toml"))
+}
+
+impl Config {
+    // Load and validate the layered configuration for the project at `root`
+    pub fn load(root: &Path) -> io::Result<Config> {
+        Self::load_layers(user_config_path(), &project_config_path(root))
+    }
+
+    // Defaults, overridden by the `user` file, overridden by the `project` file
+    fn load_layers(user: Option<PathBuf>, project: &Path) -> io::Result<Config> {
+        let mut merged = toml::Table::new();
+
+        let layers = [user, Some(project.to_path_buf())];
+        for path in layers.into_iter().flatten() {
+            let Some(mut table) = read_layer(&path)? else {
+                continue;
+            };
+            if path == project {
+                drop_user_only(&path, &mut table);
+            }
+            for (section, value) in table {
+                match (merged.get_mut(&section), value) {
+                    (Some(toml::Value::Table(existing)), toml::Value::Table(overrides)) => {
+                        existing.extend(overrides);
+                    }
+                    (_, value) => {
+                        merged.insert(section, value);
+                    }
+                }
+            }
+        }
+
+        // Each layer was validated on its own, so the merge is valid too
+        merged
+            .try_into()
+            .map_err(|e: toml::de::Error| invalid_data(e.message().to_string()))
+    }
+
+    // Absolute path of the tasks directory
+    pub fn tasks_dir(&self, root: &Path) -> PathBuf {
+        root.join(&self.tasks.dir)
+    }
+
+    pub fn archive_dir(&self, root: &Path) -> PathBuf {
+        root.join(&self.tasks.archive_dir)
+    }
+
+    pub fn id_strategy(&self) -> IdStrategy {
+        IdStrategy::parse(&self.tasks.id_strategy).unwrap_or(IdStrategy::Sequential)
+    }
+}
+
+// Parse one config file, reporting problems against that file
+fn read_layer(path: &Path) -> io::Result<Option<toml::Table>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    // The toml error already quotes the offending line and key
+    let config: Config =
+        toml::from_str(&content).map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))?;
+    validate(&config).map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))?;
+
+    let table: toml::Table =
+        toml::from_str(&content).map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))?;
+    Ok(Some(table))
+}
+
+// Ignore user-only keys found in the project file
+fn drop_user_only(path: &Path, table: &mut toml::Table) {
+    for key in USER_ONLY {
+        let (section, name) = key.split_once('.').expect("keys are section.name");
+        let removed = table
+            .get_mut(section)
+            .and_then(toml::Value::as_table_mut)
+            .and_then(|section| section.remove(name));
+        if removed.is_some() {
+            eprintln!(
+                "Warning: ignoring '{}' in {}; set it with `cdd config set {} <VALUE> --user`",
+                key,
+                path.display(),
+                key
+            );
+        }
+    }
+}
+
+// Checks that go beyond the types
+fn validate(config: &Config) -> Result<(), String> {
+    let height = config.picker.height.trim_end_matches('%');
+    if height.parse::<u16>().is_err() {
+        return Err(format!(
+            "invalid value for 'picker.height': expected a number of lines or a percentage like \"50%\", got \"{}\"",
+            config.picker.height
+        ));
+    }
+
+    if config.tasks.dir.trim().is_empty() {
+        return Err("invalid value for 'tasks.dir': must not be empty".to_string());
+    }
+    if Path::new(&config.tasks.dir).is_absolute() {
+        return Err(format!(
+            "invalid value for 'tasks.dir': must be relative to the project root, got \"{}\"",
+            config.tasks.dir
+        ));
+    }
+
+    if Path::new(&config.tasks.archive_dir).is_absolute() {
+        return Err(format!(
+            "invalid value for 'tasks.archive_dir': must be relative to the project root, got \"{}\"",
+            config.tasks.archive_dir
+        ));
+    }
+
+    if IdStrategy::parse(&config.tasks.id_strategy).is_none() {
+        return Err(format!(
+            "invalid value for 'tasks.id_strategy': expected one of {}, got \"{}\"",
+            ids::STRATEGIES.join(", "),
+            config.tasks.id_strategy
+        ));
+    }
+
+    Ok(())
+}
+
+fn key_spec(key: &str) -> io::Result<&'static KeySpec> {
+    KEYS.iter().find(|spec| spec.key == key).ok_or_else(|| {
+        let known: Vec<&str> = KEYS.iter().map(|spec| spec.key).collect();
+        invalid_input(format!(
+            "unknown key '{}' (known keys: {})",
+            key,
+            known.join(", ")
+        ))
+    })
+}
+
+// Where a key's effective value comes from
+fn lookup(root: &Path, key: &str) -> io::Result<(String, &'static str)> {
+    key_spec(key)?;
+    let (section, name) = key.split_once('.').expect("keys are section.name");
+
+    let layers = [
+        (Some(project_config_path(root)), "project"),
+        (user_config_path(), "user"),
+    ];
+    for (path, source) in layers {
+        let Some(path) = path else { continue };
+        if source == "project" && USER_ONLY.contains(&key) {
+            continue;
+        }
+        let Some(table) = read_layer(&path)? else {
+            continue;
+        };
+        if let Some(value) = table.get(section).and_then(|s| s.get(name)) {
+            return Ok((format_value(value), source));
+        }
+    }
+
+    let defaults =
+        toml::Table::try_from(Config::default()).map_err(|e| invalid_data(e.to_string()))?;
+    let value = defaults
+        .get(section)
+        .and_then(|s| s.get(name))
+        .map(format_value)
+        .unwrap_or_default();
+    Ok((value, "default"))
+}
+
+fn format_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .unwrap_or(item.to_string())
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+// `cdd config get <key>`
+pub fn get(root: &Path, key: &str) -> io::Result<String> {
+    lookup(root, key).map(|(value, _)| value)
+}
+
+// `cdd config list`: (key, value, source, description) for every key
+pub fn list(root: &Path) -> io::Result<Vec<(&'static str, String, &'static str, &'static str)>> {
+    KEYS.iter()
+        .map(|spec| {
+            let (value, source) = lookup(root, spec.key)?;
+            Ok((spec.key, value, source, spec.description))
+        })
+        .collect()
+}
+
+// `cdd config set <key> <value> [--user]`. Lists are comma-separated. The file
+// is only written if the result still validates.
+pub fn set(path: &Path, key: &str, raw_value: &str) -> io::Result<()> {
+    let spec = key_spec(key)?;
+    if USER_ONLY.contains(&key) && user_config_path().as_deref() != Some(path) {
+        return Err(invalid_input(format!(
+            "'{}' is a personal setting and only goes in the user config; add --user",
+            key
+        )));
+    }
+    let (section, name) = key.split_once('.').expect("keys are section.name");
+
+    let value: Value = match spec.kind {
+        KeyKind::String => raw_value.into(),
+        KeyKind::StringList => {
+            let mut array = Array::new();
+            for item in raw_value
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+            {
+                array.push(item);
+            }
+            Value::Array(array)
+        }
+        KeyKind::Integer => {
+            let number: u32 = raw_value.trim().parse().map_err(|_| {
+                invalid_input(format!(
+                    "invalid value for '{}': expected a whole number, got \"{}\"",
+                    key, raw_value
+                ))
+            })?;
+            i64::from(number).into()
+        }
+    };
+
+    let mut document = load_document(path)?;
+    section_table(&mut document, section)?[name] = Item::Value(value);
+
+    let config: Config = toml::from_str(&document.to_string())
+        .map_err(|e| invalid_input(format!("{}: {}", key, e.message())))?;
+    validate(&config).map_err(invalid_input)?;
+
+    save_document(path, &document)
+}
+
+pub fn record_installed_agent(root: &Path, id: &str) -> io::Result<()> {
+    let path = project_config_path(root);
+    let mut document = load_document(&path)?;
+
+    let installed = section_table(&mut document, "agents")?
+        .entry("installed")
+        .or_insert(Item::Value(Value::Array(Array::new())));
+    if let Some(array) = installed.as_array_mut() {
+        if !array.iter().any(|value| value.as_str() == Some(id)) {
+            array.push(id);
+        }
+    }
+
+    save_document(&path, &document)
+}
+
+// Called on uninstall; the file is removed if nothing else is left in it
+pub fn forget_installed_agents(root: &Path) -> io::Result<()> {
+    let path = project_config_path(root);
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let mut document = load_document(&path)?;
+    let agents = section_table(&mut document, "agents")?;
+    agents.remove("installed");
+    if agents.is_empty() {
+        document.remove("agents");
+    }
+
+    if document.is_empty() {
+        fs::remove_file(&path)
+    } else {
+        save_document(&path, &document)
+    }
+}
+
+pub fn set_default_agent(id: &str) -> io::Result<()> {
+    let path = user_config_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Home directory not found"))?;
+    set(&path, "agents.default", id)
+}
+
+fn section_table<'a>(document: &'a mut DocumentMut, section: &str) -> io::Result<&'a mut Table> {
+    document
+        .entry(section)
+        .or_insert(Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| invalid_data(format!("'{}' must be a table", section)))
+}
+
+// A missing file is an empty document; a malformed one is an error
+fn load_document(path: &Path) -> io::Result<DocumentMut> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    content
+        .parse::<DocumentMut>()
+        .map_err(|e| invalid_data(format!("{}: {}", path.display(), e)))
+}
+
+fn save_document(path: &Path, document: &DocumentMut) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, document.to_string())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A user and a project config file in a fresh directory
+    fn layers(user: &str, project: &str) -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let user_path = dir.path().join("user.toml");
+        let project_path = dir.path().join("project.toml");
+        fs::write(&user_path, user).unwrap();
+        fs::write(&project_path, project).unwrap();
+        (dir, user_path, project_path)
+    }
+
+    #[test]
+    fn project_overrides_user_overrides_defaults() {
+        let (_dir, user, project) = layers(
+            "[run]\nprompt_prefix = \"From the user\"\ntoken_budget = 1000\n\n[picker]\nheight = \"20\"\n",
+            "[run]\ntoken_budget = 500\n",
+        );
+
+        let config = Config::load_layers(Some(user), &project).unwrap();
+        assert_eq!(config.run.token_budget, 500);
+        assert_eq!(config.run.prompt_prefix, "From the user");
+        assert_eq!(config.picker.height, "20");
+        assert_eq!(config.tasks.dir, TasksConfig::default().dir);
+    }
+
+    #[test]
+    fn missing_files_leave_the_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config::load_layers(
+            Some(dir.path().join("user.toml")),
+            &dir.path().join("project.toml"),
+        )
+        .unwrap();
+        assert_eq!(config.run.token_budget, RunConfig::default().token_budget);
+        assert_eq!(config.agents.default, "");
+    }
+
+    #[test]
+    fn a_project_cannot_set_the_default_agent() {
+        let (_dir, user, project) = layers(
+            "[agents]\ndefault = \"claude\"\n",
+            "[agents]\ndefault = \"evil\"\ninstalled = [\"aider\"]\n",
+        );
+
+        let config = Config::load_layers(Some(user.clone()), &project).unwrap();
+        assert_eq!(config.agents.default, "claude");
+        assert_eq!(config.agents.installed, ["aider"]);
+
+        fs::remove_file(&user).unwrap();
+        let config = Config::load_layers(Some(user), &project).unwrap();
+        assert_eq!(config.agents.default, "");
+    }
+
+    #[test]
+    fn errors_name_the_file_they_come_from() {
+        let (_dir, user, project) = layers("", "[picker]\nheight = \"tall\"\n");
+        let error = Config::load_layers(Some(user), &project).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with(&project.display().to_string()));
+        assert!(error.to_string().contains("'picker.height'"));
+    }
+}
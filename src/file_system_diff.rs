@@ -0,0 +1,207 @@
+// Parser for the "File System Diff" section of a task file, e.g.
+//
+//     project/
+//     ├── src/
+//     │   ├── + new_module.rs
+//     │   └── ~ modified_file.rs
+//     └── - old_deprecated_file.rs
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Modified,
+    Removed,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    // Path relative to the project root (the tree's first line is the root)
+    pub path: String,
+    pub change: Change,
+    pub is_dir: bool,
+}
+
+const SECTION_HEADING: &str = "## File System Diff";
+
+// Collect the entries of every File System Diff tree in `markdown`. Lines
+// that are notes rather than paths (e.g. "+ Added dependencies: serde") are
+// skipped.
+pub fn parse_file_system_diff(markdown: &str) -> Vec<DiffEntry> {
+    let mut entries: Vec<DiffEntry> = Vec::new();
+    let mut in_section = false;
+    let mut in_fence = false;
+    let mut stack: Vec<String> = Vec::new();
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("```") {
+            if in_section {
+                in_fence = !in_fence;
+                stack.clear();
+            }
+            continue;
+        }
+
+        if !in_fence && trimmed.starts_with("## ") {
+            in_section = trimmed == SECTION_HEADING;
+            continue;
+        }
+
+        if !in_section || !in_fence {
+            continue;
+        }
+
+        // Tree lines look like "│   ├── name"; lines without a connector are the root
+        let Some((prefix, rest)) = line.split_once("── ") else {
+            stack.clear();
+            continue;
+        };
+
+        // Each nesting level is four characters wide ("│   " or "    ") plus the connector
+        let depth = prefix.chars().count().saturating_sub(1) / 4;
+        let (change, name) = split_change_marker(rest.trim());
+        let name = strip_note(name);
+
+        stack.truncate(depth);
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            continue;
+        }
+
+        let is_dir = name.ends_with('/');
+        let name = name.trim_end_matches('/');
+        stack.push(name.to_string());
+
+        entries.push(DiffEntry {
+            path: stack.join("/"),
+            change,
+            is_dir,
+        });
+    }
+
+    // Entries with children are directories even when written without a trailing slash
+    let dirs: Vec<String> = entries
+        .iter()
+        .filter(|entry| {
+            entries
+                .iter()
+                .any(|other| other.path.starts_with(&format!("{}/", entry.path)))
+        })
+        .map(|entry| entry.path.clone())
+        .collect();
+    for entry in &mut entries {
+        if dirs.contains(&entry.path) {
+            entry.is_dir = true;
+        }
+    }
+
+    entries
+}
+
+// Files (not directories) the task adds or edits, without duplicates
+pub fn touched_files(entries: &[DiffEntry]) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for entry in entries {
+        if !entry.is_dir && entry.change != Change::Removed && !files.contains(&entry.path) {
+            files.push(entry.path.clone());
+        }
+    }
+    files
+}
+
+fn split_change_marker(name: &str) -> (Change, &str) {
+    if let Some(rest) = name.strip_prefix("+ ") {
+        (Change::Added, rest.trim())
+    } else if let Some(rest) = name.strip_prefix("~ ") {
+        (Change::Modified, rest.trim())
+    } else if let Some(rest) = name.strip_prefix("- ") {
+        (Change::Removed, rest.trim())
+    } else {
+        (Change::Unchanged, name)
+    }
+}
+
+// Drop trailing "# comment" notes after a path
+fn strip_note(name: &str) -> &str {
+    match name.find(" #") {
+        Some(index) => name[..index].trim(),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, change: Change, is_dir: bool) -> DiffEntry {
+        DiffEntry {
+            path: path.to_string(),
+            change,
+            is_dir,
+        }
+    }
+
+    #[test]
+    fn parses_the_task_template_tree() {
+        let template = include_str!("../.context/_reference/templates/TASK.md");
+        let entries = parse_file_system_diff(template);
+        assert_eq!(
+            entries,
+            [
+                entry("src", Change::Unchanged, true),
+                entry("src/new_module.rs", Change::Added, false),
+                entry("src/modified_file.rs", Change::Modified, false),
+                entry("src/components", Change::Unchanged, true),
+                entry("src/components/new_component.rs", Change::Added, false),
+                entry("tests", Change::Unchanged, true),
+                entry("tests/test_new_feature.rs", Change::Added, false),
+                entry("old_deprecated_file.rs", Change::Removed, false),
+            ]
+        );
+        assert_eq!(
+            touched_files(&entries),
+            [
+                "src/new_module.rs",
+                "src/modified_file.rs",
+                "src/components/new_component.rs",
+                "tests/test_new_feature.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_notes_and_trailing_comments() {
+        let markdown = "## File System Diff\n\n```\nproject/\n├── + Added dependencies: serde\n├── ~ Cargo.toml  # add serde\n└── src\n    └── + lib.rs\n```\n";
+        assert_eq!(
+            parse_file_system_diff(markdown),
+            [
+                entry("Cargo.toml", Change::Modified, false),
+                entry("src", Change::Unchanged, true),
+                entry("src/lib.rs", Change::Added, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_trees_outside_the_section() {
+        let markdown = "## Goals\n\n```\nproject/\n└── + goal.rs\n```\n\n## File System Diff\n\n```\nproject/\n└── + main.rs\n```\n\n## Lessons Learned\n\n```\nproject/\n└── + lesson.rs\n```\n";
+        assert_eq!(
+            parse_file_system_diff(markdown),
+            [entry("main.rs", Change::Added, false)]
+        );
+    }
+
+    #[test]
+    fn reads_every_task_of_a_bundle() {
+        let markdown = "## File System Diff\n\n```\na/\n└── + one.rs\n```\n\n## File System Diff\n\n```\nb/\n└── - two.rs\n```\n";
+        assert_eq!(
+            parse_file_system_diff(markdown),
+            [
+                entry("one.rs", Change::Added, false),
+                entry("two.rs", Change::Removed, false),
+            ]
+        );
+        assert_eq!(touched_files(&parse_file_system_diff(markdown)), ["one.rs"]);
+    }
+}
@@ -0,0 +1,55 @@
+// File system helpers shared by uninstall and migrate
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Every file below `dir` (nothing if it doesn't exist)
+pub fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Tar archive at `archive` holding `files` under their paths relative to `root`
+pub fn write_tar(root: &Path, archive: &Path, files: &[PathBuf]) -> io::Result<()> {
+    let mut builder = tar::Builder::new(fs::File::create(archive)?);
+    for path in files {
+        builder.append_path_with_name(path, relative(root, path))?;
+    }
+    builder.finish()
+}
+
+// Timestamp used in backup and snapshot names; sorts chronologically
+pub fn timestamp() -> String {
+    chrono::Local::now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+// Remove empty directories between `path` and the project root
+pub fn remove_empty_parents(root: &Path, path: &Path) {
+    for dir in path.ancestors().skip(1) {
+        if dir == root || !dir.starts_with(root) {
+            break;
+        }
+        // Fails (and stops) on the first directory that still has content
+        if fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+pub fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
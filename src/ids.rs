@@ -0,0 +1,590 @@
+// Task id allocation and `cdd ids`. The strategy comes from the
+// `tasks.id_strategy` setting:
+//
+//     sequential   TASK_001, TASK_002, ... across the whole project
+//     per-project  TASK_CORE_001, numbered separately for each project
+//     timestamp    TASK_20250105143000, the creation time
+//     hash         TASK_3FA91C, so parallel branches rarely collide
+
+use crate::files::{collect_files, relative};
+use crate::manifest::sha256_hex;
+use crate::task::{self, Task};
+use chrono::NaiveDate;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const STRATEGIES: &[&str] = &["sequential", "per-project", "timestamp", "hash"];
+
+// Longest number the sequential strategy counts; timestamp ids are longer
+const MAX_SEQUENTIAL_DIGITS: usize = 6;
+
+// Hex digits in a hash id
+const HASH_LEN: usize = 6;
+
+// Characters of the project name used in per-project ids
+const MAX_PROJECT_KEY_LEN: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum IdStrategy {
+    Sequential,
+    PerProject,
+    Timestamp,
+    Hash,
+}
+
+impl IdStrategy {
+    pub fn parse(name: &str) -> Option<IdStrategy> {
+        match name {
+            "sequential" => Some(IdStrategy::Sequential),
+            "per-project" => Some(IdStrategy::PerProject),
+            "timestamp" => Some(IdStrategy::Timestamp),
+            "hash" => Some(IdStrategy::Hash),
+            _ => None,
+        }
+    }
+}
+
+// A task file as far as ids are concerned; files that don't parse still
+// count with the id from their name
+struct TaskFile {
+    path: PathBuf,
+    id: String,
+    created: Option<NaiveDate>,
+    project: Option<String>,
+    title: Option<String>,
+}
+
+// Number in a TASK_NNN id
+pub fn task_number(id: &str) -> Option<u64> {
+    let digits = id.strip_prefix("TASK_")?;
+    if digits.is_empty()
+        || digits.len() > MAX_SEQUENTIAL_DIGITS
+        || !digits.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+// CORE_001-backend -> CORE
+fn project_key(project: &str) -> String {
+    let key: String = project
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .take(MAX_PROJECT_KEY_LEN)
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if key.is_empty() {
+        "MAIN".to_string()
+    } else {
+        key
+    }
+}
+
+fn task_files(tasks_dir: &Path) -> io::Result<Vec<TaskFile>> {
+    let mut files = Vec::new();
+    collect_files(tasks_dir, &mut files)?;
+    files.sort();
+
+    let mut tasks = Vec::new();
+    for path in files
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+    {
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        let task = match Task::parse(&path, &content) {
+            Ok(task) => TaskFile {
+                id: task.id(),
+                created: task.created,
+                project: task.project,
+                title: task.title,
+                path,
+            },
+            Err(_) => TaskFile {
+                id: task::file_id(&path),
+                created: None,
+                project: None,
+                title: None,
+                path,
+            },
+        };
+        if task.id.starts_with("TASK_") {
+            tasks.push(task);
+        }
+    }
+    Ok(tasks)
+}
+
+// A new id for a task in `project`, unused by any task below `tasks_dir` and
+// by `reserved` (ids handed out but not written yet)
+pub fn allocate(
+    strategy: IdStrategy,
+    tasks_dir: &Path,
+    project: &str,
+    title: &str,
+    reserved: &[String],
+) -> io::Result<String> {
+    let mut taken: Vec<String> = reserved.to_vec();
+    for task in task_files(tasks_dir)? {
+        taken.push(task::file_id(&task.path));
+        taken.push(task.id);
+    }
+
+    let id = match strategy {
+        IdStrategy::Sequential => {
+            let highest = taken.iter().filter_map(|id| task_number(id)).max();
+            format!("TASK_{:03}", highest.unwrap_or(0) + 1)
+        }
+        IdStrategy::PerProject => {
+            let prefix = format!("TASK_{}_", project_key(project));
+            let highest = taken
+                .iter()
+                .filter_map(|id| id.strip_prefix(&prefix)?.parse::<u64>().ok())
+                .max();
+            format!("{}{:03}", prefix, highest.unwrap_or(0) + 1)
+        }
+        IdStrategy::Timestamp => {
+            let mut time = chrono::Local::now().naive_local();
+            loop {
+                let id = format!("TASK_{}", time.format("%Y%m%d%H%M%S"));
+                if !taken.contains(&id) {
+                    break id;
+                }
+                time += chrono::Duration::seconds(1);
+            }
+        }
+        IdStrategy::Hash => {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos())
+                .unwrap_or_default();
+            let mut attempt = 0;
+            loop {
+                let seed = format!("{}\n{}\n{}\n{}", project, title, nanos, attempt);
+                let hash = sha256_hex(seed.as_bytes())[..HASH_LEN].to_ascii_uppercase();
+                let id = format!("TASK_{}", hash);
+                // All-digit hashes would read as sequential numbers
+                if !hash.chars().all(|c| c.is_ascii_digit()) && !taken.contains(&id) {
+                    break id;
+                }
+                attempt += 1;
+            }
+        }
+    };
+
+    Ok(id)
+}
+
+// Ids used by more than one task, with their files in path order
+fn duplicates(tasks_dir: &Path) -> io::Result<Vec<(String, Vec<TaskFile>)>> {
+    let mut groups: Vec<(String, Vec<TaskFile>)> = Vec::new();
+    for task in task_files(tasks_dir)? {
+        match groups.iter_mut().find(|(id, _)| *id == task.id) {
+            Some((_, group)) => group.push(task),
+            None => groups.push((task.id.clone(), vec![task])),
+        }
+    }
+
+    groups.retain(|(_, group)| group.len() > 1);
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(groups)
+}
+
+// `cdd ids check`: report duplicate ids, returning how many were found
+pub fn check(root: &Path, tasks_dir: &Path) -> io::Result<usize> {
+    let duplicates = duplicates(tasks_dir)?;
+    if duplicates.is_empty() {
+        println!("✓ No duplicate task ids");
+        return Ok(0);
+    }
+
+    for (id, group) in &duplicates {
+        println!("✗ {} is used by {} tasks:", id, group.len());
+        for task in group {
+            println!("    {}", relative(root, &task.path));
+        }
+    }
+    println!("\nRun 'cdd ids fix' to renumber the newer tasks.");
+    Ok(duplicates.len())
+}
+
+// `cdd ids fix`: the oldest task keeps a duplicated id, the others get new
+// ids from `strategy`. Their files are renamed and rewritten, and links to
+// their file names in other tasks follow. A bare mention of the old id could
+// mean either task, so those are only reported.
+pub fn fix(root: &Path, tasks_dir: &Path, strategy: IdStrategy, dry_run: bool) -> io::Result<()> {
+    let duplicates = duplicates(tasks_dir)?;
+    if duplicates.is_empty() {
+        println!("✓ No duplicate task ids");
+        return Ok(());
+    }
+
+    let mut reserved: Vec<String> = Vec::new();
+    for (id, mut group) in duplicates {
+        // Undated tasks count as the newest
+        group.sort_by(|a, b| {
+            (a.created.is_none(), a.created, &a.path).cmp(&(
+                b.created.is_none(),
+                b.created,
+                &b.path,
+            ))
+        });
+        let kept = &group[0];
+        println!("🔧 {} stays with {}", id, relative(root, &kept.path));
+
+        for task in &group[1..] {
+            let new_id = allocate(
+                strategy,
+                tasks_dir,
+                task.project.as_deref().unwrap_or_default(),
+                task.title.as_deref().unwrap_or_default(),
+                &reserved,
+            )?;
+            reserved.push(new_id.clone());
+
+            let new_path = renamed(&task.path, &id, &new_id);
+            println!("  ✓ {} -> {}", relative(root, &task.path), new_id);
+            if new_path != task.path {
+                println!("    renamed to {}", relative(root, &new_path));
+            }
+
+            let content = fs::read_to_string(&task.path)?;
+            let (content, _) =
+                replace_word(&content, &file_stem(&task.path), &file_stem(&new_path));
+            let (content, _) = replace_word(&content, &id, &new_id);
+            if !dry_run {
+                fs::write(&task.path, content)?;
+                fs::rename(&task.path, &new_path)?;
+            }
+
+            update_references(root, tasks_dir, &id, task, &new_path, kept, dry_run)?;
+        }
+    }
+
+    if dry_run {
+        println!("\nDry run: nothing was written.");
+    }
+    Ok(())
+}
+
+// Point links to the renumbered file at its new name, and list the bare
+// mentions of the old id left for the user to check
+fn update_references(
+    root: &Path,
+    tasks_dir: &Path,
+    id: &str,
+    task: &TaskFile,
+    new_path: &Path,
+    kept: &TaskFile,
+    dry_run: bool,
+) -> io::Result<()> {
+    rename_links(root, tasks_dir, &task.path, new_path, dry_run)?;
+
+    let skip = [task.path.as_path(), new_path, kept.path.as_path()];
+    for (path, line) in mentions(tasks_dir, id, &skip)? {
+        println!(
+            "    ⚠ {}:{} mentions {}, check which task it means",
+            relative(root, &path),
+            line,
+            id
+        );
+    }
+
+    Ok(())
+}
+
+// Lines naming `id` on its own, outside the files in `skip`
+fn mentions(tasks_dir: &Path, id: &str, skip: &[&Path]) -> io::Result<Vec<(PathBuf, usize)>> {
+    let mut found = Vec::new();
+    for path in markdown_files(tasks_dir)? {
+        if skip.contains(&path.as_path()) {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        for (index, line) in content.lines().enumerate() {
+            if !find_word(line, id).is_empty() {
+                found.push((path.clone(), index + 1));
+            }
+        }
+    }
+    Ok(found)
+}
+
+// Rewrite links to `old_path`'s file name in other task files after it was
+// renamed to `new_path`
+pub fn rename_links(
+    root: &Path,
+    tasks_dir: &Path,
+    old_path: &Path,
+    new_path: &Path,
+    dry_run: bool,
+) -> io::Result<()> {
+    let old_stem = file_stem(old_path);
+    let new_stem = file_stem(new_path);
+    if old_stem == new_stem {
+        return Ok(());
+    }
+
+    for path in markdown_files(tasks_dir)?
+        .iter()
+        .filter(|path| path.as_path() != old_path && path.as_path() != new_path)
+    {
+        let content = fs::read_to_string(path)?;
+        let (updated, count) = replace_word(&content, &old_stem, &new_stem);
+        if count > 0 {
+            println!(
+                "    updated {} reference(s) in {}",
+                count,
+                relative(root, path)
+            );
+            if !dry_run {
+                fs::write(path, &updated)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn markdown_files(tasks_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_files(tasks_dir, &mut files)?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "md"));
+    files.sort();
+    Ok(files)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+// TASK_007-fix-login.md -> TASK_008-fix-login.md; other names are kept
+fn renamed(path: &Path, old_id: &str, new_id: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    match name.strip_prefix(old_id) {
+        Some(rest) if rest.starts_with(['-', '.']) => {
+            path.with_file_name(format!("{}{}", new_id, rest))
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+// Byte offsets of `word` in `text` where it isn't part of a longer id or
+// file name (TASK_007 doesn't match TASK_0071 or TASK_007-add-auth)
+fn find_word(text: &str, word: &str) -> Vec<usize> {
+    let is_id_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    text.match_indices(word)
+        .map(|(start, _)| start)
+        .filter(|start| {
+            let before = text[..*start].chars().next_back();
+            let after = text[start + word.len()..].chars().next();
+            !before.is_some_and(is_id_char) && !after.is_some_and(|c| is_id_char(c) || c == '-')
+        })
+        .collect()
+}
+
+fn replace_word(text: &str, from: &str, to: &str) -> (String, usize) {
+    let starts = find_word(text, from);
+    let mut output = String::new();
+    let mut last = 0;
+    for start in &starts {
+        output.push_str(&text[last..*start]);
+        output.push_str(to);
+        last = start + from.len();
+    }
+    output.push_str(&text[last..]);
+    (output, starts.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_task(dir: &Path, name: &str, id: &str, project: &str, created: &str) -> PathBuf {
+        let path = dir.join(name);
+        let content = format!(
+            "---\ntask_id: {}\ntitle: Task\nproject: {}\ncreated: \"{}\"\n---\n\n# {}: Task\n",
+            id, project, created, id
+        );
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn sequential_ids_follow_the_highest_number() {
+        let dir = tempfile::tempdir().unwrap();
+        write_task(
+            dir.path(),
+            "TASK_001-a.md",
+            "TASK_001",
+            "core",
+            "2026-01-01",
+        );
+        write_task(
+            dir.path(),
+            "TASK_007-b.md",
+            "TASK_007",
+            "core",
+            "2026-01-01",
+        );
+        // Timestamp ids are too long to count as sequential numbers
+        write_task(
+            dir.path(),
+            "TASK_20260101120000-c.md",
+            "TASK_20260101120000",
+            "core",
+            "2026-01-01",
+        );
+
+        let id = allocate(IdStrategy::Sequential, dir.path(), "core", "", &[]).unwrap();
+        assert_eq!(id, "TASK_008");
+        let reserved = ["TASK_008".to_string()];
+        let id = allocate(IdStrategy::Sequential, dir.path(), "core", "", &reserved).unwrap();
+        assert_eq!(id, "TASK_009");
+    }
+
+    #[test]
+    fn per_project_ids_count_each_project_separately() {
+        let dir = tempfile::tempdir().unwrap();
+        write_task(
+            dir.path(),
+            "TASK_CORE_002-a.md",
+            "TASK_CORE_002",
+            "core",
+            "2026-01-01",
+        );
+
+        let id = allocate(IdStrategy::PerProject, dir.path(), "core-backend", "", &[]).unwrap();
+        assert_eq!(id, "TASK_CORE_003");
+        let id = allocate(IdStrategy::PerProject, dir.path(), "web", "", &[]).unwrap();
+        assert_eq!(id, "TASK_WEB_001");
+        let id = allocate(IdStrategy::PerProject, dir.path(), "", "", &[]).unwrap();
+        assert_eq!(id, "TASK_MAIN_001");
+    }
+
+    #[test]
+    fn timestamp_ids_skip_taken_seconds() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = allocate(IdStrategy::Timestamp, dir.path(), "", "", &[]).unwrap();
+        let digits = first.strip_prefix("TASK_").unwrap();
+        assert_eq!(digits.len(), 14);
+        assert!(digits.chars().all(|c| c.is_ascii_digit()));
+
+        let second = allocate(
+            IdStrategy::Timestamp,
+            dir.path(),
+            "",
+            "",
+            std::slice::from_ref(&first),
+        )
+        .unwrap();
+        assert!(second > first);
+    }
+
+    #[test]
+    fn hash_ids_are_short_hex_and_never_all_digits() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut reserved = Vec::new();
+        for _ in 0..20 {
+            let id = allocate(IdStrategy::Hash, dir.path(), "core", "Login", &reserved).unwrap();
+            let hash = id.strip_prefix("TASK_").unwrap();
+            assert_eq!(hash.len(), HASH_LEN);
+            assert!(hash
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_lowercase()));
+            assert!(!hash.chars().all(|c| c.is_ascii_digit()));
+            assert!(!reserved.contains(&id));
+            reserved.push(id);
+        }
+    }
+
+    #[test]
+    fn duplicates_are_found_across_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("web")).unwrap();
+        write_task(
+            dir.path(),
+            "TASK_001-a.md",
+            "TASK_001",
+            "core",
+            "2026-01-01",
+        );
+        write_task(
+            dir.path(),
+            "web/TASK_001-b.md",
+            "TASK_001",
+            "web",
+            "2026-02-01",
+        );
+        write_task(
+            dir.path(),
+            "TASK_002-c.md",
+            "TASK_002",
+            "core",
+            "2026-01-01",
+        );
+
+        let found = duplicates(dir.path()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "TASK_001");
+        assert_eq!(found[0].1.len(), 2);
+        assert_eq!(check(dir.path(), dir.path()).unwrap(), 1);
+    }
+
+    #[test]
+    fn fix_renumbers_the_newer_task_and_rewrites_links_to_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let older = write_task(root, "TASK_001-a.md", "TASK_001", "core", "2026-01-01");
+        let newer = write_task(root, "TASK_001-b.md", "TASK_001", "core", "2026-02-01");
+        write_task(root, "TASK_002-c.md", "TASK_002", "core", "2026-01-01");
+        let notes = root.join("TASK_003-notes.md");
+        let notes_content =
+            "---\ntask_id: TASK_003\n---\n\nSee [b](TASK_001-b.md).\nFollows TASK_001.\n";
+        fs::write(&notes, notes_content).unwrap();
+        let older_content = fs::read_to_string(&older).unwrap();
+
+        // The bare mention is reported before anything is renamed
+        let skip = [older.as_path(), newer.as_path()];
+        assert_eq!(
+            mentions(root, "TASK_001", &skip).unwrap(),
+            [(notes.clone(), 6)]
+        );
+
+        fix(root, root, IdStrategy::Sequential, false).unwrap();
+
+        let moved = root.join("TASK_004-b.md");
+        assert!(!newer.exists());
+        let content = fs::read_to_string(&moved).unwrap();
+        assert!(content.contains("task_id: TASK_004\n"));
+        assert!(content.contains("# TASK_004: Task"));
+        assert_eq!(fs::read_to_string(&older).unwrap(), older_content);
+
+        let notes_content = fs::read_to_string(&notes).unwrap();
+        assert!(notes_content.contains("[b](TASK_004-b.md)"));
+        assert!(notes_content.contains("Follows TASK_001."));
+        assert_eq!(check(root, root).unwrap(), 0);
+    }
+
+    #[test]
+    fn dry_run_fix_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write_task(root, "TASK_001-a.md", "TASK_001", "core", "2026-01-01");
+        let newer = write_task(root, "TASK_001-b.md", "TASK_001", "core", "2026-02-01");
+
+        fix(root, root, IdStrategy::Sequential, true).unwrap();
+
+        assert!(newer.exists());
+        assert_eq!(check(root, root).unwrap(), 1);
+    }
+}
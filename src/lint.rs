@@ -0,0 +1,860 @@
+// `cdd lint`: the checks from _reference/commands/cdd-review-tasks.md that
+// don't need judgement, run over task files with stable rule ids so the
+// result can gate pre-commit and CI.
+
+use crate::config::Config;
+use crate::files::{collect_files, relative};
+use crate::new_task::frontmatter_value;
+use crate::status;
+use crate::task::{self, Task, MAX_GOALS, SECTIONS};
+use serde_json::json;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// Frontmatter keys every task needs
+const REQUIRED_KEYS: &[&str] = &["task_id", "title", "project", "created"];
+
+// Statuses of tasks nobody has started on
+const NEW_STATUSES: &[&str] = &["draft", "ready"];
+
+// Upper bound on fix passes over one file, in case fixes keep producing work
+const MAX_FIX_PASSES: usize = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+pub struct Rule {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "CDD001",
+        name: "invalid-task",
+        severity: Severity::Error,
+        description: "The task file has no valid frontmatter or a broken structure",
+    },
+    Rule {
+        id: "CDD002",
+        name: "missing-frontmatter-key",
+        severity: Severity::Error,
+        description: "Frontmatter must have task_id, title, project and created",
+    },
+    Rule {
+        id: "CDD003",
+        name: "missing-section",
+        severity: Severity::Error,
+        description:
+            "Tasks need Summary, Goals, File System Diff, Lessons Learned and Validation sections",
+    },
+    Rule {
+        id: "CDD004",
+        name: "code-block",
+        severity: Severity::Error,
+        description: "Code blocks belong in the codebase; only File System Diff may have one",
+    },
+    Rule {
+        id: "CDD005",
+        name: "too-many-goals",
+        severity: Severity::Warning,
+        description: "More than 4 goals usually means the task should be split",
+    },
+    Rule {
+        id: "CDD006",
+        name: "lessons-on-new-task",
+        severity: Severity::Warning,
+        description: "Lessons Learned stays empty until work on the task has started",
+    },
+    Rule {
+        id: "CDD007",
+        name: "validation-without-just-dev",
+        severity: Severity::Warning,
+        description: "Validation should include `just dev`",
+    },
+    Rule {
+        id: "CDD008",
+        name: "unknown-status",
+        severity: Severity::Warning,
+        description: "status must be draft, ready, in-progress, in-review, done or abandoned",
+    },
+];
+
+// A mechanical change that resolves a diagnostic
+enum Fix {
+    // Set "key: value" in the frontmatter
+    SetKey(String, String),
+    // Add an empty section before `before` (or at the end)
+    AddSection(&'static str, Option<String>),
+    // Add a line to the end of a section
+    AppendLine(String, String),
+    // Remove the template's <placeholder> lines from a section
+    RemovePlaceholders(String),
+}
+
+pub struct Diagnostic {
+    pub rule: &'static Rule,
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    fix: Option<Fix>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+    Sarif,
+}
+
+struct LintArgs {
+    paths: Vec<PathBuf>,
+    fix: bool,
+    format: Format,
+}
+
+fn rule(id: &str) -> &'static Rule {
+    RULES
+        .iter()
+        .find(|rule| rule.id == id)
+        .expect("rule ids are defined in RULES")
+}
+
+// Entry point for `cdd lint [PATH]... [--fix] [--format text|json|sarif]`;
+// returns the number of errors left
+pub fn lint(root: &Path, config: &Config, args: &[String]) -> io::Result<usize> {
+    let args = parse_args(args);
+    let targets = if args.paths.is_empty() {
+        vec![config.tasks_dir(root)]
+    } else {
+        args.paths.iter().map(|path| root.join(path)).collect()
+    };
+
+    let mut files = Vec::new();
+    for target in &targets {
+        if target.is_dir() {
+            collect_files(target, &mut files)?;
+        } else if target.exists() {
+            files.push(target.clone());
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist", relative(root, target)),
+            ));
+        }
+    }
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "md"));
+    files.sort();
+    files.dedup();
+
+    let mut diagnostics = Vec::new();
+    let mut fixed = 0;
+    for path in &files {
+        let mut content = fs::read_to_string(path)?;
+        if args.fix {
+            let (updated, count) = fix_content(path, &content);
+            if count > 0 {
+                fs::write(path, &updated)?;
+                if args.format == Format::Text {
+                    println!("✓ Fixed {} problem(s) in {}", count, relative(root, path));
+                }
+                fixed += count;
+                content = updated;
+            }
+        }
+        diagnostics.extend(check(path, &content));
+    }
+
+    match args.format {
+        Format::Text => print_text(root, &diagnostics, files.len(), fixed),
+        Format::Json => print_json(root, &diagnostics),
+        Format::Sarif => print_sarif(root, &diagnostics),
+    }
+
+    Ok(diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.rule.severity == Severity::Error)
+        .count())
+}
+
+fn parse_args(args: &[String]) -> LintArgs {
+    let mut lint = LintArgs {
+        paths: Vec::new(),
+        fix: false,
+        format: Format::Text,
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--fix" => lint.fix = true,
+            "--format" => {
+                lint.format = match args.get(i + 1).map(String::as_str) {
+                    Some("text") => Format::Text,
+                    Some("json") => Format::Json,
+                    Some("sarif") => Format::Sarif,
+                    _ => {
+                        eprintln!("Error: --format must be text, json or sarif");
+                        print_usage();
+                        process::exit(1);
+                    }
+                };
+                i += 1;
+            }
+            flag if flag.starts_with('-') => {
+                eprintln!("Unknown option for 'cdd lint': {}", flag);
+                print_usage();
+                process::exit(1);
+            }
+            path => lint.paths.push(PathBuf::from(path)),
+        }
+        i += 1;
+    }
+
+    lint
+}
+
+fn print_usage() {
+    eprintln!("Usage: cdd lint [PATH]... [--fix] [--format text|json|sarif]");
+}
+
+// Every problem in one task file
+fn check(path: &Path, content: &str) -> Vec<Diagnostic> {
+    let diagnostic =
+        |id: &str, line: usize, column: usize, message: String, fix: Option<Fix>| Diagnostic {
+            rule: rule(id),
+            path: path.to_path_buf(),
+            line,
+            column,
+            message,
+            fix,
+        };
+
+    let task = match Task::parse(path, content) {
+        Ok(task) => task,
+        Err(e) => return vec![diagnostic("CDD001", e.line, e.column, e.message, None)],
+    };
+    let mut diagnostics = Vec::new();
+
+    for key in REQUIRED_KEYS {
+        if task.field(key).is_some_and(|field| !field.value.is_empty()) {
+            continue;
+        }
+        let fix = guess_value(&task, content, key).map(|value| Fix::SetKey(key.to_string(), value));
+        diagnostics.push(diagnostic(
+            "CDD002",
+            1,
+            1,
+            format!("frontmatter is missing '{}'", key),
+            fix,
+        ));
+    }
+
+    if let (Some(field), Some(value)) = (task.field("status"), task.status.as_deref()) {
+        match status::canonical(value) {
+            Some(status) if status == value => {}
+            // Older spellings like "completed" have an exact replacement
+            Some(status) => diagnostics.push(diagnostic(
+                "CDD008",
+                field.line,
+                field.column,
+                format!("status '{}' is spelled '{}' now", value, status),
+                Some(Fix::SetKey("status".to_string(), status.to_string())),
+            )),
+            None => diagnostics.push(diagnostic(
+                "CDD008",
+                field.line,
+                field.column,
+                format!("unknown status '{}'", value),
+                None,
+            )),
+        }
+    }
+
+    for (index, title) in SECTIONS.iter().enumerate() {
+        if task.section(title).is_some() {
+            continue;
+        }
+        // Lessons Learned may be empty, so adding the heading is all it needs
+        let fix = (*title == "Lessons Learned").then(|| {
+            let before = SECTIONS[index + 1..]
+                .iter()
+                .find_map(|next| task.section(next))
+                .map(|section| section.title.clone());
+            Fix::AddSection(title, before)
+        });
+        diagnostics.push(diagnostic(
+            "CDD003",
+            1,
+            1,
+            format!("missing '## {}' section", title),
+            fix,
+        ));
+    }
+
+    for line in code_blocks(content) {
+        diagnostics.push(diagnostic(
+            "CDD004",
+            line,
+            1,
+            "code block outside File System Diff".to_string(),
+            None,
+        ));
+    }
+
+    if let Some(goals) = task.section("Goals") {
+        let count = task::strip_comments(&goals.content)
+            .lines()
+            .filter(|line| is_list_item(line))
+            .count();
+        if count > MAX_GOALS {
+            diagnostics.push(diagnostic(
+                "CDD005",
+                goals.line,
+                1,
+                format!("{} goals, at most {} keep a task focused", count, MAX_GOALS),
+                None,
+            ));
+        }
+    }
+
+    if let Some(lessons) = task.section("Lessons Learned") {
+        let text = task::strip_comments(&lessons.content);
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        if !lines.is_empty() && lines.iter().all(|line| is_placeholder(line)) {
+            diagnostics.push(diagnostic(
+                "CDD006",
+                lessons.line,
+                1,
+                "Lessons Learned still has the template placeholder".to_string(),
+                Some(Fix::RemovePlaceholders(lessons.title.clone())),
+            ));
+        } else if !lines.is_empty() && is_new(&task) {
+            diagnostics.push(diagnostic(
+                "CDD006",
+                lessons.line,
+                1,
+                "Lessons Learned should be empty on a task that hasn't been started".to_string(),
+                None,
+            ));
+        }
+    }
+
+    if let Some(validation) = task.section("Validation") {
+        if !validation.content.contains("just dev") {
+            diagnostics.push(diagnostic(
+                "CDD007",
+                validation.line,
+                1,
+                "Validation does not run `just dev`".to_string(),
+                Some(Fix::AppendLine(
+                    validation.title.clone(),
+                    "- `just dev` - Development environment runs without errors".to_string(),
+                )),
+            ));
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    diagnostics
+}
+
+// A task counts as new when its status says so, or when it has no status and
+// was never updated
+fn is_new(task: &Task) -> bool {
+    match task.status.as_deref().map(status::canonical) {
+        Some(Some(status)) => NEW_STATUSES.contains(&status),
+        Some(None) => false,
+        None => task.updated.is_none(),
+    }
+}
+
+// Lines opening a code fence anywhere but in File System Diff
+fn code_blocks(content: &str) -> Vec<usize> {
+    let mut found = Vec::new();
+    let mut in_fence = false;
+    let mut section = "";
+
+    // The frontmatter and its two `---` lines
+    let skip = task::split_frontmatter(content)
+        .0
+        .map_or(0, |frontmatter| frontmatter.lines().count() + 2);
+    for (index, line) in content.lines().enumerate().skip(skip) {
+        if line.trim_start().starts_with("```") {
+            if !in_fence && !section.eq_ignore_ascii_case("File System Diff") {
+                found.push(index + 1);
+            }
+            in_fence = !in_fence;
+        } else if let Some(title) = line.strip_prefix("## ").filter(|_| !in_fence) {
+            section = title.trim();
+        }
+    }
+    found
+}
+
+fn is_list_item(line: &str) -> bool {
+    let numbered = line.split_once(". ").is_some_and(|(number, _)| {
+        !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+    });
+    line.starts_with("- ") || line.starts_with("* ") || numbered
+}
+
+// "<One paragraph describing ...>" left over from TASK.md
+fn is_placeholder(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with('<') && line.ends_with('>')
+}
+
+// Values that can be recovered from the file name or the "# ID: Title"
+// heading, quoted for the frontmatter where YAML needs it
+fn guess_value(task: &Task, content: &str, key: &str) -> Option<String> {
+    match key {
+        "task_id" => Some(task::file_id(&task.path)).filter(|id| id.starts_with("TASK_")),
+        "title" => content
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .and_then(|heading| heading.split_once(": "))
+            .map(|(_, title)| title.trim())
+            .filter(|title| !title.is_empty() && !is_placeholder(title))
+            .map(frontmatter_value),
+        _ => None,
+    }
+}
+
+// Apply fixable diagnostics one at a time, re-checking in between so line
+// numbers stay accurate
+fn fix_content(path: &Path, content: &str) -> (String, usize) {
+    let mut content = content.to_string();
+    let mut count = 0;
+
+    for _ in 0..MAX_FIX_PASSES {
+        let Ok(task) = Task::parse(path, &content) else {
+            break;
+        };
+        let diagnostics = check(path, &content);
+        let Some(fix) = diagnostics
+            .iter()
+            .find_map(|diagnostic| diagnostic.fix.as_ref())
+        else {
+            break;
+        };
+        content = apply_fix(&task, &content, fix);
+        count += 1;
+    }
+
+    (content, count)
+}
+
+fn apply_fix(task: &Task, content: &str, fix: &Fix) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    // Where each section's text ends: the next heading, or the end of the file
+    let section_end = |title: &str| -> Option<(usize, usize)> {
+        let index = task
+            .sections
+            .iter()
+            .position(|section| section.title == title)?;
+        let start = task.sections[index].line;
+        let end = task
+            .sections
+            .get(index + 1)
+            .map(|next| next.line - 1)
+            .unwrap_or(lines.len());
+        Some((start, end))
+    };
+
+    match fix {
+        Fix::SetKey(key, value) => return task::set_field(content, key, value),
+        Fix::AddSection(title, before) => {
+            let at = before
+                .as_deref()
+                .and_then(|before| task.section(before))
+                .map(|section| section.line - 1)
+                .unwrap_or(lines.len());
+            let mut added = vec![format!("## {}", title), String::new()];
+            if at == lines.len() && lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                added.insert(0, String::new());
+            }
+            lines.splice(at..at, added);
+        }
+        Fix::AppendLine(title, line) => {
+            if let Some((start, end)) = section_end(title) {
+                let last = (start..end)
+                    .rev()
+                    .find(|index| !lines[*index].trim().is_empty())
+                    .unwrap_or(start - 1);
+                lines.insert(last + 1, line.clone());
+            }
+        }
+        Fix::RemovePlaceholders(title) => {
+            if let Some((start, end)) = section_end(title) {
+                let mut index = end;
+                while index > start {
+                    index -= 1;
+                    if is_placeholder(&lines[index]) {
+                        lines.remove(index);
+                        // Don't leave a doubled blank line behind
+                        if lines.get(index).is_some_and(|line| line.trim().is_empty())
+                            && index > 0
+                            && lines[index - 1].trim().is_empty()
+                        {
+                            lines.remove(index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
+}
+
+fn print_text(root: &Path, diagnostics: &[Diagnostic], files: usize, fixed: usize) {
+    for diagnostic in diagnostics {
+        println!(
+            "{}:{}:{}: {}[{}] {} ({})",
+            relative(root, &diagnostic.path),
+            diagnostic.line,
+            diagnostic.column,
+            diagnostic.rule.severity.as_str(),
+            diagnostic.rule.id,
+            diagnostic.message,
+            diagnostic.rule.name
+        );
+    }
+
+    if diagnostics.is_empty() {
+        println!("✓ {} task file(s) checked, no problems", files);
+        return;
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.rule.severity == Severity::Error)
+        .count();
+    let fixable = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.fix.is_some())
+        .count();
+    println!(
+        "\n✗ {} problem(s) in {} task file(s): {} error(s), {} warning(s)",
+        diagnostics.len(),
+        files,
+        errors,
+        diagnostics.len() - errors
+    );
+    if fixable > 0 && fixed == 0 {
+        println!("  {} can be fixed with 'cdd lint --fix'", fixable);
+    }
+}
+
+fn print_json(root: &Path, diagnostics: &[Diagnostic]) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json_report(root, diagnostics)).unwrap_or_default()
+    );
+}
+
+fn json_report(root: &Path, diagnostics: &[Diagnostic]) -> serde_json::Value {
+    let items: Vec<serde_json::Value> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            json!({
+                "rule": diagnostic.rule.id,
+                "name": diagnostic.rule.name,
+                "severity": diagnostic.rule.severity.as_str(),
+                "path": relative(root, &diagnostic.path),
+                "line": diagnostic.line,
+                "column": diagnostic.column,
+                "message": diagnostic.message,
+                "fixable": diagnostic.fix.is_some(),
+            })
+        })
+        .collect();
+    serde_json::Value::Array(items)
+}
+
+fn print_sarif(root: &Path, diagnostics: &[Diagnostic]) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&sarif_log(root, diagnostics)).unwrap_or_default()
+    );
+}
+
+// SARIF 2.1.0, as read by GitHub code scanning
+fn sarif_log(root: &Path, diagnostics: &[Diagnostic]) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "name": rule.name,
+                "shortDescription": { "text": rule.description },
+                "defaultConfiguration": { "level": rule.severity.as_str() },
+            })
+        })
+        .collect();
+    let results: Vec<serde_json::Value> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            json!({
+                "ruleId": diagnostic.rule.id,
+                "level": diagnostic.rule.severity.as_str(),
+                "message": { "text": diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": relative(root, &diagnostic.path) },
+                        "region": {
+                            "startLine": diagnostic.line,
+                            "startColumn": diagnostic.column,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cdd",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/piotryordanov/context-driven-development",
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "---
+task_id: TASK_001
+title: Add auth
+project: core
+status: in-progress
+created: \"2026-01-01\"
+---
+
+# TASK_001: Add auth
+
+## Summary
+
+Add a login page.
+
+## Goals
+
+- Users can log in
+
+## File System Diff
+
+```
+src/
+└── auth.rs  # NEW
+```
+
+## Lessons Learned
+
+## Validation
+
+- `just dev` - Development environment runs without errors
+";
+
+    fn path() -> &'static Path {
+        Path::new(".context/tasks/TASK_001-add-auth.md")
+    }
+
+    // Rule id and line of each diagnostic
+    fn found(content: &str) -> Vec<(&'static str, usize)> {
+        check(path(), content)
+            .iter()
+            .map(|diagnostic| (diagnostic.rule.id, diagnostic.line))
+            .collect()
+    }
+
+    #[test]
+    fn a_complete_task_has_no_problems() {
+        assert_eq!(found(VALID), []);
+    }
+
+    #[test]
+    fn cdd001_task_without_frontmatter() {
+        assert_eq!(found("# TASK_001: Add auth\n"), [("CDD001", 1)]);
+        let unclosed = VALID.replacen("---\n\n#", "\n#", 1);
+        assert_eq!(found(&unclosed), [("CDD001", 1)]);
+    }
+
+    #[test]
+    fn cdd002_missing_frontmatter_key() {
+        let content = VALID.replace("project: core\n", "");
+        assert_eq!(found(&content), [("CDD002", 1)]);
+        let content = VALID.replace("project: core\n", "project: \"\"\n");
+        assert_eq!(found(&content), [("CDD002", 1)]);
+    }
+
+    #[test]
+    fn cdd003_missing_section() {
+        let content = VALID.replace("## Goals\n\n- Users can log in\n\n", "");
+        assert_eq!(found(&content), [("CDD003", 1)]);
+    }
+
+    #[test]
+    fn cdd004_code_block_outside_file_system_diff() {
+        let content = VALID.replace(
+            "Add a login page.\n",
+            "Add a login page.\n\n```rust\nfn login() {}\n```\n",
+        );
+        assert_eq!(found(&content), [("CDD004", 15)]);
+    }
+
+    #[test]
+    fn cdd005_too_many_goals() {
+        let content = VALID.replace(
+            "- Users can log in\n",
+            "- One\n- Two\n3. Three\n* Four\n- Five\n",
+        );
+        assert_eq!(found(&content), [("CDD005", 15)]);
+    }
+
+    #[test]
+    fn cdd006_lessons_on_a_new_task() {
+        let started = VALID.replace(
+            "## Lessons Learned\n",
+            "## Lessons Learned\n\n- Sessions expire\n",
+        );
+        assert_eq!(found(&started), []);
+        let new = started.replace("status: in-progress", "status: ready");
+        assert_eq!(found(&new), [("CDD006", 26)]);
+        let placeholder = VALID.replace(
+            "## Lessons Learned\n",
+            "## Lessons Learned\n\n<What was learned>\n",
+        );
+        assert_eq!(found(&placeholder), [("CDD006", 26)]);
+    }
+
+    #[test]
+    fn cdd007_validation_without_just_dev() {
+        let content = VALID.replace("`just dev`", "`cargo test`");
+        assert_eq!(found(&content), [("CDD007", 28)]);
+    }
+
+    #[test]
+    fn cdd008_unknown_or_old_status() {
+        let unknown = VALID.replace("status: in-progress", "status: someday");
+        assert_eq!(found(&unknown), [("CDD008", 5)]);
+        let old = VALID.replace("status: in-progress", "status: completed");
+        let diagnostics = check(path(), &old);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].fix.is_some());
+    }
+
+    #[test]
+    fn fix_repairs_everything_mechanical_and_then_has_nothing_left() {
+        let broken = VALID
+            .replace("task_id: TASK_001\n", "")
+            .replace("status: in-progress", "status: wip")
+            .replace("## Lessons Learned\n\n", "")
+            .replace(
+                "`just dev` - Development environment runs without errors",
+                "`cargo test`",
+            );
+        let (fixed, count) = fix_content(path(), &broken);
+
+        assert_eq!(count, 4);
+        assert_eq!(found(&fixed), []);
+        assert!(fixed.contains("task_id: TASK_001\n"));
+        assert!(fixed.contains("status: in-progress\n"));
+        assert!(fixed.contains("## File System Diff"));
+        assert!(fixed.contains("## Lessons Learned\n\n## Validation"));
+        assert!(fixed.contains("- `cargo test`\n- `just dev`"));
+        assert_eq!(fix_content(path(), &fixed), (fixed.clone(), 0));
+    }
+
+    #[test]
+    fn sarif_log_shape() {
+        let content = VALID.replace("project: core\n", "");
+        let diagnostics = check(path(), &content);
+        let log = sarif_log(Path::new(""), &diagnostics);
+
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "cdd");
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().map(Vec::len),
+            Some(RULES.len())
+        );
+        assert_eq!(
+            run["results"],
+            json!([{
+                "ruleId": "CDD002",
+                "level": "error",
+                "message": { "text": "frontmatter is missing 'project'" },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": ".context/tasks/TASK_001-add-auth.md" },
+                        "region": { "startLine": 1, "startColumn": 1 },
+                    },
+                }],
+            }])
+        );
+    }
+
+    #[test]
+    fn json_report_marks_fixable_problems() {
+        let content = VALID.replace("`just dev`", "`cargo test`");
+        let report = json_report(Path::new(""), &check(path(), &content));
+        assert_eq!(
+            report,
+            json!([{
+                "rule": "CDD007",
+                "name": "validation-without-just-dev",
+                "severity": "warning",
+                "path": ".context/tasks/TASK_001-add-auth.md",
+                "line": 28,
+                "column": 1,
+                "message": "Validation does not run `just dev`",
+                "fixable": true,
+            }])
+        );
+    }
+
+    #[test]
+    fn fix_quotes_a_title_recovered_from_the_heading() {
+        let path = Path::new(".context/tasks/TASK_001-auth.md");
+        let content = "---\ntask_id: TASK_001\nproject: core\ncreated: \"2026-01-01\"\n---\n\n\
+                       # TASK_001: Add user auth: v2 #1\n";
+        let (fixed, _) = fix_content(path, content);
+
+        assert!(fixed.contains("title: \"Add user auth: v2 #1\"\n"));
+        let task = Task::parse(path, &fixed).unwrap();
+        assert_eq!(task.title.as_deref(), Some("Add user auth: v2 #1"));
+    }
+}
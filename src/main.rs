@@ -1,24 +1,70 @@
+mod actions;
+mod bundle;
+mod config;
+mod file_system_diff;
+mod files;
+mod ids;
+mod lint;
+mod manifest;
+mod merge;
+mod migrate;
+mod new_task;
+mod picker;
+mod preview;
+mod profiles;
+mod prompt;
+mod rules;
+mod status;
+mod task;
+mod uninstall;
+mod upgrade;
+
 use include_dir::{include_dir, Dir};
-use inquire::Select;
-use skim::prelude::*;
+use inquire::{MultiSelect, Select};
 use std::env;
 use std::fs;
-use std::io::Cursor;
+use std::io::IsTerminal;
 use std::path::Path;
 use std::process;
 
+use config::Config;
+use manifest::Manifest;
+use picker::{Action, Resolution};
+use profiles::{AgentProfile, Launch, PlannedFile, ProfileRegistry, Reference, ReferenceFile};
+use task::Task;
+use upgrade::UpgradeOptions;
+
 // Embed the .context/_reference directory at compile time
 static REFERENCE_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/.context/_reference");
 
+// The _reference directory of earlier releases, one folder per version, to
+// recognise untouched files in projects installed before the manifest existed
+static RELEASED_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/released");
+
 // Get the current package version
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Exit codes of `cdd run TARGET` when the target doesn't name a single task
+const EXIT_NOT_FOUND: i32 = 2;
+const EXIT_AMBIGUOUS: i32 = 3;
+
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let registry = ProfileRegistry::load(&env::current_dir().unwrap_or_default());
 
     // Parse arguments
-    let mut profile: Option<&str> = None;
+    let mut profiles: Vec<&dyn AgentProfile> = Vec::new();
+    let mut all_profiles = false;
+    let mut agent: Option<&dyn AgentProfile> = None;
+    let mut set_default = false;
+    let mut purge = false;
+    let mut rollback = false;
+    let mut options = UpgradeOptions::default();
     let mut command: Option<String> = None;
+    let mut command_args: Vec<String> = Vec::new();
+    let mut filters = picker::Filters::default();
+    let mut target: Option<String> = None;
+    let mut select_one = false;
 
     if args.len() > 1 {
         let mut i = 1;
@@ -26,42 +72,148 @@ fn main() {
             match args[i].as_str() {
                 "-p" | "--profile" => {
                     if i + 1 < args.len() {
-                        let profile_arg = args[i + 1].to_lowercase();
-                        profile = match profile_arg.as_str() {
-                            "claude" | "claudecode" | "claude-code" => Some("Claude Code"),
-                            "opencode" | "open-code" => Some("OpenCode"),
-                            _ => {
-                                eprintln!("Error: Unknown profile '{}'", args[i + 1]);
-                                eprintln!("Valid profiles: claude, opencode");
-                                process::exit(1);
-                            }
-                        };
+                        profiles.push(find_profile_or_exit(&registry, &args[i + 1]));
                         i += 2;
                     } else {
                         eprintln!("Error: --profile requires a value");
-                        eprintln!("Usage: cdd install --profile <claude|opencode>");
+                        eprintln!(
+                            "Usage: cdd install --profile <{}>",
+                            registry.ids().join("|")
+                        );
+                        process::exit(1);
+                    }
+                }
+                "--all" => {
+                    all_profiles = true;
+                    i += 1;
+                }
+                "-a" | "--agent" => {
+                    if i + 1 < args.len() {
+                        agent = Some(find_profile_or_exit(&registry, &args[i + 1]));
+                        i += 2;
+                    } else {
+                        eprintln!("Error: --agent requires a value");
+                        eprintln!("Usage: cdd run --agent <{}>", registry.ids().join("|"));
+                        process::exit(1);
+                    }
+                }
+                "--status" | "--project" | "--tag" => {
+                    let Some(value) = args.get(i + 1) else {
+                        eprintln!("Error: {} requires a value", args[i]);
+                        eprintln!("Usage: cdd run [--status <STATUS>] [--project <PROJECT>] [--tag <TAG>] [--all]");
                         process::exit(1);
+                    };
+                    let values = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|value| !value.is_empty());
+                    match args[i].as_str() {
+                        "--status" => {
+                            for value in values {
+                                let Some(status) = status::canonical(value) else {
+                                    eprintln!("Error: Unknown status '{}'", value);
+                                    eprintln!("Valid statuses: {}", status::STATUSES.join(", "));
+                                    process::exit(1);
+                                };
+                                filters.statuses.push(status.to_string());
+                            }
+                        }
+                        "--project" => filters.projects.extend(values.map(str::to_string)),
+                        _ => filters.tags.extend(values.map(str::to_string)),
                     }
+                    i += 2;
+                }
+                "-1" | "--select-1" => {
+                    select_one = true;
+                    i += 1;
+                }
+                "--set-default" => {
+                    set_default = true;
+                    i += 1;
+                }
+                "--purge" => {
+                    purge = true;
+                    i += 1;
+                }
+                "--rollback" => {
+                    rollback = true;
+                    i += 1;
+                }
+                "--force" => {
+                    options.force = true;
+                    i += 1;
+                }
+                "--dry-run" => {
+                    options.dry_run = true;
+                    i += 1;
                 }
                 "install" | "setup" => {
                     command = Some("install".to_string());
                     i += 1;
                 }
-                "uninstall" | "rm" | "remove" => {
-                    if let Err(e) = uninstall() {
-                        eprintln!("Error during uninstall: {}", e);
+                "run" | "prompt" => {
+                    command = Some(args[i].clone());
+                    i += 1;
+                }
+                "new" => {
+                    // `cdd new` parses its own flags
+                    command = Some("new".to_string());
+                    command_args = args[i + 1..].to_vec();
+                    break;
+                }
+                "ids" => {
+                    // `cdd ids check|fix` parses its own arguments
+                    command = Some("ids".to_string());
+                    command_args = args[i + 1..].to_vec();
+                    break;
+                }
+                "status" | "start" | "done" => {
+                    // Status commands take a task and parse their own flags
+                    command = Some(args[i].clone());
+                    command_args = args[i + 1..].to_vec();
+                    break;
+                }
+                "lint" => {
+                    // `cdd lint` parses its own flags
+                    command = Some("lint".to_string());
+                    command_args = args[i + 1..].to_vec();
+                    break;
+                }
+                "migrate" => {
+                    command = Some("migrate".to_string());
+                    i += 1;
+                }
+                "update" | "upgrade" => {
+                    command = Some("update".to_string());
+                    i += 1;
+                }
+                "config" => {
+                    if let Err(e) = config_command(&args[i + 1..]) {
+                        eprintln!("Error: {}", e);
                         process::exit(1);
                     }
                     return;
                 }
+                "uninstall" | "rm" | "remove" => {
+                    command = Some("uninstall".to_string());
+                    i += 1;
+                }
                 "--version" | "-v" => {
                     println!("cdd (context-driven-development) {}", VERSION);
                     return;
                 }
                 "--help" | "-h" => {
-                    print_help();
+                    print_help(&registry);
                     return;
                 }
+                // `cdd run TASK_042`, `cdd run path/to/task.md` or `cdd run "login bug"`
+                arg if matches!(command.as_deref(), Some("run" | "prompt"))
+                    && target.is_none()
+                    && !arg.starts_with('-') =>
+                {
+                    target = Some(arg.to_string());
+                    i += 1;
+                }
                 _ => {
                     eprintln!("Unknown command: {}", args[i]);
                     eprintln!("Run 'cdd --help' for usage information.");
@@ -71,30 +223,219 @@ fn main() {
         }
     }
 
+    let current_dir = env::current_dir().unwrap_or_default();
+
+    // A broken config shouldn't block removing cdd; fall back to the defaults
+    if command.as_deref() == Some("uninstall") {
+        let config = Config::load(&current_dir).unwrap_or_default();
+        let shipped = shipped_files(&registry, &current_dir);
+        if let Err(e) = uninstall::uninstall(&registry, &config, &current_dir, &shipped, purge) {
+            eprintln!("Error during uninstall: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let config = match Config::load(&current_dir) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error in configuration: {}", e);
+            eprintln!("Fix the file or use 'cdd config set <key> <value>'.");
+            process::exit(1);
+        }
+    };
+
     // If install/setup command, run installation
     if command.as_deref() == Some("install") {
-        install(profile);
+        if all_profiles {
+            // Project profiles wait until the user has trusted them
+            profiles = registry.trusted().collect();
+            for profile in registry.iter().filter(|p| !profiles::is_trusted(*p)) {
+                println!(
+                    "Note: Skipping {}, a profile from this repository you haven't trusted yet.",
+                    profile.name()
+                );
+            }
+        }
+        install(&registry, &config, profiles, options);
+        return;
+    }
+
+    if command.as_deref() == Some("new") {
+        if let Err(e) = new_task::new_task(
+            &current_dir,
+            &config,
+            &task_template(&current_dir),
+            &command_args,
+        ) {
+            eprintln!("Error creating task: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if command.as_deref() == Some("ids") {
+        match ids_command(&current_dir, &config, &command_args) {
+            Ok(0) => {}
+            Ok(_) => process::exit(1),
+            Err(e) => {
+                eprintln!("Error checking task ids: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(name @ ("status" | "start" | "done")) = command.as_deref() {
+        if let Err(e) = status::status_command(&current_dir, &config, name, &command_args) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if command.as_deref() == Some("prompt") {
+        let Some(query) = target.as_deref() else {
+            eprintln!("Usage: cdd prompt <TASK> [--agent <PROFILE>]");
+            process::exit(1);
+        };
+        // Without --agent, the agent `cdd run` would pick without asking
+        let installed = registry.installed(&current_dir, &config.agents.installed);
+        let agent_id = match agent {
+            Some(profile) => profile.id(),
+            None => match installed
+                .iter()
+                .find(|profile| profile.matches(&config.agents.default))
+            {
+                Some(profile) => profile.id(),
+                None if installed.len() == 1 => installed[0].id(),
+                None => "",
+            },
+        };
+        if let Err(e) = prompt::prompt_command(&current_dir, &config, agent_id, query) {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if command.as_deref() == Some("lint") {
+        match lint::lint(&current_dir, &config, &command_args) {
+            Ok(0) => {}
+            Ok(_) => process::exit(1),
+            Err(e) => {
+                eprintln!("Error linting tasks: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if command.as_deref() == Some("migrate") {
+        let result = if rollback {
+            migrate::rollback(&current_dir)
+        } else {
+            migrate::migrate(&current_dir, &config, VERSION, options.dry_run)
+        };
+        if let Err(e) = result {
+            eprintln!("Error during migration: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if command.as_deref() == Some("update") {
+        if let Err(e) = update(&registry, &config, options) {
+            eprintln!("Error during update: {}", e);
+            process::exit(1);
+        }
         return;
     }
 
     // Default: run task selector
-    if let Err(e) = run_task() {
+    filters.all = all_profiles;
+    if let Err(e) = run_task(
+        &registry,
+        &config,
+        agent,
+        set_default,
+        filters,
+        target.as_deref(),
+        select_one,
+    ) {
         eprintln!("Error running task selector: {}", e);
         process::exit(1);
     }
 }
 
-fn install(profile: Option<&str>) {
-    // Get choice - either from profile flag or interactive prompt
-    let choice = if let Some(profile_choice) = profile {
-        profile_choice
+fn find_profile_or_exit<'a>(registry: &'a ProfileRegistry, name: &str) -> &'a dyn AgentProfile {
+    match registry.find(name) {
+        Some(found) => found,
+        None => {
+            eprintln!("Error: Unknown profile '{}'", name);
+            eprintln!("Valid profiles: {}", registry.ids().join(", "));
+            process::exit(1);
+        }
+    }
+}
+
+fn install(
+    registry: &ProfileRegistry,
+    config: &Config,
+    profiles: Vec<&dyn AgentProfile>,
+    options: UpgradeOptions,
+) {
+    let current_dir = env::current_dir().unwrap_or_default();
+    let mut manifest = match Manifest::load(&current_dir) {
+        Ok(manifest) => manifest.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("Error reading install manifest: {}", e);
+            process::exit(1);
+        }
+    };
+
+    // Bring an older .context layout up to date before extracting into it
+    if migrate::has_pending(&current_dir, VERSION) {
+        if let Err(e) = migrate::migrate(&current_dir, config, VERSION, options.dry_run) {
+            eprintln!("Error migrating .context: {}", e);
+            process::exit(1);
+        }
+        println!();
+    }
+
+    // Only show what would happen to .context/_reference
+    if options.dry_run {
+        if let Err(e) = ensure_context_extracted(config, &mut manifest, options) {
+            eprintln!("Error planning .context update: {}", e);
+            process::exit(1);
+        }
+        println!("\nDry run: nothing was written.");
+        return;
+    }
+
+    // Get choices - either from profile flags or interactive prompt
+    let choices = if !profiles.is_empty() {
+        profiles
     } else {
-        let options = vec!["Claude Code", "OpenCode"];
-        match Select::new("Choose your development environment:", options)
-            .without_help_message()
+        let options: Vec<&str> = registry.iter().map(|p| p.name()).collect();
+        // Pre-select the configured default agent
+        let defaults: Vec<usize> = registry
+            .iter()
+            .position(|p| p.matches(&config.agents.default))
+            .into_iter()
+            .collect();
+        match MultiSelect::new("Choose your development environment(s):", options)
+            .with_default(&defaults)
             .prompt()
         {
-            Ok(choice) => choice,
+            Ok(names) if !names.is_empty() => names
+                .into_iter()
+                .map(|name| registry.find(name).expect("selected profile is registered"))
+                .collect(),
+            Ok(_) => {
+                eprintln!("No environment selected.");
+                process::exit(1);
+            }
             Err(_) => {
                 eprintln!("Selection cancelled.");
                 process::exit(1);
@@ -103,35 +444,91 @@ fn install(profile: Option<&str>) {
     };
 
     // Ensure .context is extracted and up-to-date
-    if let Err(e) = ensure_context_extracted() {
+    if let Err(e) = ensure_context_extracted(config, &mut manifest, options) {
         eprintln!("Error setting up .context: {}", e);
         process::exit(1);
     }
 
-    // Copy command files to profile directory
-    if let Err(e) = copy_commands(choice) {
-        eprintln!("Error copying commands: {}", e);
-        process::exit(1);
+    for choice in choices {
+        // Copy command files to profile directory
+        if let Err(e) = copy_commands(choice, &mut manifest) {
+            eprintln!("Error copying commands for {}: {}", choice.name(), e);
+            process::exit(1);
+        }
+
+        // Remember the profile so `cdd run` can offer it
+        if let Err(e) = config::record_installed_agent(&current_dir, choice.id()) {
+            eprintln!("Error updating .context/config.toml: {}", e);
+            process::exit(1);
+        }
+
+        println!("\n✓ Setup complete for {}", choice.name());
     }
 
-    println!("\n✓ Setup complete for {}", choice);
+    // Record what was written so uninstall can leave user changes alone
+    if let Err(e) = manifest.save(&current_dir) {
+        eprintln!("Error writing install manifest: {}", e);
+        process::exit(1);
+    }
 }
 
-fn print_help() {
+fn print_help(registry: &ProfileRegistry) {
     println!("cdd (context-driven-development) {}", VERSION);
     println!();
     println!("USAGE:");
     println!("    cdd [COMMAND] [OPTIONS]");
     println!();
     println!("COMMANDS:");
-    println!("    (no args)                Fuzzy find and select a task (default)");
+    println!("    (no args), run           Fuzzy find and select a task (default)");
+    println!("    run <TASK|PATH|QUERY>    Launch a task by id or path, or pick among tasks matching a query");
     println!("    install, setup           Install/setup CDD in current directory");
+    println!("    new                      Create a task from the TASK.md template");
+    println!("    prompt <TASK>            Print the prompt run would send (add --agent to pick the template)");
+    println!("    status [TASK [STATUS]]   List task statuses, or show/change one task's status");
+    println!("    start <TASK>             Mark a task in-progress");
+    println!("    done <TASK>              Mark a task done");
+    println!("    lint [PATH]...           Check task files against the task rules (--fix, --format json|sarif)");
+    println!("    ids check                Find task ids used by more than one task");
+    println!("    ids fix                  Renumber duplicate task ids (add --dry-run to preview)");
+    println!("    update, upgrade          Update installed files to this version of cdd");
+    println!("    migrate                  Move an older .context layout to the current one");
     println!("    uninstall, rm, remove    Remove CDD files from current directory");
+    println!("    config list              Show all settings and where they come from");
+    println!("    config get <KEY>         Print the effective value of a setting");
+    println!("    config set <KEY> <VALUE> Change a setting (add --user for your user config)");
     println!("    --version, -v            Print version information");
     println!("    --help, -h               Print this help message");
     println!();
     println!("OPTIONS:");
-    println!("    -p, --profile <PROFILE>  Specify profile: claude or opencode");
+    println!(
+        "    -p, --profile <PROFILE>  Specify profile (repeatable): {}",
+        registry.ids().join(", ")
+    );
+    println!("    --all                    With install: every profile; with run: include done and abandoned tasks");
+    println!("    --status <STATUS>        With run: only tasks with this status (repeatable, or comma-separated)");
+    println!("    --project <PROJECT>      With run: only tasks of this project");
+    println!("    --tag <TAG>              With run: only tasks with this tag");
+    println!(
+        "    -1, --select-1           With run QUERY: launch a single match without the picker"
+    );
+    println!("    -a, --agent <PROFILE>    Agent to launch for the selected task");
+    println!("    --set-default            Remember the launched agent as your default");
+    println!("    --purge                  With uninstall: also remove tasks and config");
+    println!("    --rollback               With migrate: undo the latest migration");
+    println!(
+        "    --force                  With install: overwrite locally modified reference files"
+    );
+    println!("    --dry-run                With install: show what would change without writing");
+    println!();
+    println!("PICKER KEYS:");
+    println!("    enter                    Launch the agent with the task, or every marked task");
+    println!("    tab / shift-tab          Mark several tasks or folders");
+    println!("    ctrl-e                   Open the task in $EDITOR");
+    println!("    ctrl-d / ctrl-a          Mark the task done / move it to the archive");
+    println!("    ctrl-r                   Rename the task and its file");
+    println!("    ctrl-y                   Print the task's path and exit");
+    println!("    alt-s / alt-p / alt-t    Cycle the status / project / tag filter");
+    println!("    alt-a                    Show or hide done and abandoned tasks");
     println!();
     println!("DESCRIPTION:");
     println!("    A tool to help you take your context-driven development to the next level.");
@@ -141,455 +538,775 @@ fn print_help() {
     println!("    cdd install              # Interactive install - choose profile");
     println!("    cdd install -p opencode  # Install with OpenCode profile");
     println!("    cdd install -p claude    # Install with Claude Code profile");
-    println!("    cdd uninstall            # Remove CDD files");
+    println!("    cdd install -p cursor    # Install with Cursor profile");
+    println!("    cdd install -p gemini    # Install with Gemini CLI profile");
+    println!("    cdd install -p codex     # Install with Codex CLI profile");
+    println!("    cdd install -p copilot   # Install with GitHub Copilot profile");
+    println!("    cdd install -p aider     # Install with Aider profile");
+    println!("    cdd install -p claude -p opencode  # Install several profiles");
+    println!("    cdd run --agent claude   # Launch Claude Code for the selected task");
+    println!("    cdd run TASK_042         # Launch a task without the picker");
+    println!("    cdd prompt TASK_042      # Print the rendered prompt for a task");
+    println!("    cdd run -1 \"login bug\"   # Launch the only task matching \"login bug\"");
+    println!(
+        "    cdd run --status in-review --project CORE  # Pick among CORE tasks awaiting review"
+    );
+    println!("    cdd new                  # Create a task, answering a few questions");
+    println!("    cdd new --title \"Add auth\" --goal \"Login works\" --no-input  # Scripted");
+    println!("    cdd config set picker.height 80%  # Make the task picker taller");
+    println!("    cdd config set tasks.id_strategy hash  # Ids that don't collide across branches");
+    println!("    cdd ids check            # List duplicate task ids after a merge");
+    println!("    cdd done TASK_007        # Mark TASK_007 done and bump its updated date");
+    println!("    cdd lint --fix           # Check tasks and fix what can be fixed mechanically");
+    println!("    cdd lint --format sarif > cdd.sarif  # Report for code scanning in CI");
+    println!("    cdd install --dry-run    # Preview an upgrade of .context/_reference");
+    println!("    cdd update               # Update commands and rules after upgrading cdd");
+    println!("    cdd migrate --dry-run    # Show how an old .context layout would be moved");
+    println!("    cdd uninstall            # Remove CDD files, keeping tasks and edits");
+    println!("    cdd uninstall --purge    # Remove everything, including tasks");
     println!("    cdd --version            # Show version");
 }
 
-fn uninstall() -> std::io::Result<()> {
+fn config_command(args: &[String]) -> std::io::Result<()> {
     let current_dir = env::current_dir()?;
+    let user = args.iter().any(|arg| arg == "--user");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--user")
+        .collect();
+
+    match positional.as_slice() {
+        [] | ["list"] => {
+            for (key, value, source, description) in config::list(&current_dir)? {
+                println!("{} = {:?}  ({})", key, value, source);
+                println!("    {}", description);
+            }
+        }
+        ["get", key] => {
+            println!("{}", config::get(&current_dir, key)?);
+        }
+        ["set", key, value] => {
+            let path = if user {
+                config::user_config_path().ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::NotFound, "Home directory not found")
+                })?
+            } else {
+                config::project_config_path(&current_dir)
+            };
+            config::set(&path, key, value)?;
+            println!("✓ Set {} = {:?} in {}", key, value, path.display());
+        }
+        _ => {
+            eprintln!("Usage: cdd config [list | get <KEY> | set <KEY> <VALUE> [--user]]");
+            process::exit(1);
+        }
+    }
+
+    Ok(())
+}
 
-    println!("🗑️  Uninstalling CDD files...");
+// `cdd ids check` / `cdd ids fix [--dry-run]`; returns the number of
+// duplicate ids left
+fn ids_command(root: &Path, config: &Config, args: &[String]) -> std::io::Result<usize> {
+    let tasks_dir = config.tasks_dir(root);
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--dry-run")
+        .collect();
+
+    match positional.as_slice() {
+        [] | ["check"] => ids::check(root, &tasks_dir),
+        ["fix"] => {
+            ids::fix(root, &tasks_dir, config.id_strategy(), dry_run)?;
+            Ok(0)
+        }
+        _ => {
+            eprintln!("Usage: cdd ids [check | fix [--dry-run]]");
+            process::exit(1);
+        }
+    }
+}
 
-    let mut removed_count = 0;
+// Bring installed files up to date with this version of cdd. Untouched
+// files are replaced; edited ones are shown as a diff to accept, keep or merge.
+fn update(
+    registry: &ProfileRegistry,
+    config: &Config,
+    options: UpgradeOptions,
+) -> std::io::Result<()> {
+    let current_dir = env::current_dir()?;
+    let version_file = current_dir.join(".context/.version");
 
-    // Remove .context directory completely
-    let context_path = current_dir.join(".context");
-    if context_path.exists() {
-        fs::remove_dir_all(&context_path)?;
-        println!("  ✓ Removed .context/");
-        removed_count += 1;
-    }
-
-    // Remove command folders from .claude and .opencode
-    let profile_configs = vec![
-        (current_dir.join(".claude"), vec!["commands"]),
-        (current_dir.join(".opencode"), vec!["command"]),
-    ];
-
-    for (profile_dir, folders) in profile_configs {
-        if profile_dir.exists() {
-            let profile_name = profile_dir.file_name().unwrap().to_string_lossy();
-
-            for folder in folders {
-                let folder_path = profile_dir.join(folder);
-                if folder_path.exists() {
-                    fs::remove_dir_all(&folder_path)?;
-                    println!("  ✓ Removed {}/{}/", profile_name, folder);
-                    removed_count += 1;
-                }
-            }
+    let Ok(previous) = fs::read_to_string(&version_file) else {
+        eprintln!("Error: CDD is not installed in this directory.");
+        eprintln!("Run 'cdd install' first to initialize the project.");
+        process::exit(1);
+    };
+    let previous = previous.trim().to_string();
 
-            // Check if profile directory is now empty, if so remove it
-            if profile_dir.read_dir()?.next().is_none() {
-                fs::remove_dir(&profile_dir)?;
-                println!("  ✓ Removed {}/ (was empty)", profile_name);
-            }
-        }
+    if migrate::has_pending(&current_dir, VERSION) {
+        migrate::migrate(&current_dir, config, VERSION, options.dry_run)?;
+        println!();
     }
 
-    if removed_count == 0 {
-        println!("  No CDD files found to remove.");
+    if previous == VERSION {
+        println!("🔄 Checking CDD files against version {}...", VERSION);
     } else {
         println!(
-            "\n✅ Uninstall complete! Removed {} item(s).",
-            removed_count
+            "🔄 Updating CDD files from version {} to {}...",
+            previous, VERSION
         );
-        println!("Note: This only removed CDD files from the current directory.");
+    }
+
+    let mut manifest = Manifest::load(&current_dir)?.unwrap_or_default();
+    let reference = embedded_reference();
+    let theirs_label = format!("cdd {}", VERSION);
+
+    let mut installed = registry.installed(&current_dir, &config.agents.installed);
+    installed.retain(|profile| profiles::is_trusted(*profile));
+    let released = released_files(&installed, &current_dir);
+    let mut changes = upgrade::plan(
+        &current_dir,
+        &manifest,
+        reference_planned_files(&current_dir),
+        &released,
+        manifest::REFERENCE_OWNER,
+        options.force,
+        &theirs_label,
+    );
+    for profile in &installed {
+        changes.extend(upgrade::plan(
+            &current_dir,
+            &manifest,
+            profile.planned_files(&current_dir, &reference),
+            &released,
+            profile.id(),
+            options.force,
+            &theirs_label,
+        ));
+    }
+
+    if options.dry_run {
+        upgrade::print_plan(&current_dir, &changes);
+        upgrade::print_summary(&changes);
+        println!("\nDry run: nothing was written.");
+        return Ok(());
+    }
+
+    upgrade::review(&current_dir, &mut changes, &theirs_label)?;
+
+    println!();
+    upgrade::print_plan(&current_dir, &changes);
+    upgrade::apply(&current_dir, &mut manifest, &changes)?;
+
+    // Refresh managed sections in shared files (AGENTS.md, ...)
+    for profile in &installed {
+        profile.install(&current_dir, &reference)?;
+    }
+
+    manifest.save(&current_dir)?;
+    fs::write(&version_file, VERSION)?;
+
+    println!("\n✅ Update complete since version {}:", previous);
+    upgrade::print_summary(&changes);
+
+    let conflicts = upgrade::conflict_count(&changes);
+    if conflicts > 0 {
         println!(
-            "To uninstall the cdd binary itself, run: cargo uninstall context-driven-development"
+            "⚠️  {} file(s) have merge conflicts; resolve them before committing",
+            conflicts
         );
     }
 
     Ok(())
 }
 
-fn ensure_context_extracted() -> std::io::Result<()> {
+fn ensure_context_extracted(
+    config: &Config,
+    manifest: &mut Manifest,
+    options: UpgradeOptions,
+) -> std::io::Result<()> {
     let current_dir = env::current_dir()?;
     let context_path = current_dir.join(".context");
     let version_file = context_path.join(".version");
 
-    // Always extract _reference to ensure it's up-to-date
-    // This allows users to get latest rules and templates on every run
+    // Always extract _reference to ensure it's up-to-date. Files the user
+    // edited are merged with the new version instead of overwritten.
     println!(
         "📦 Extracting .context/_reference files (version {})...",
         VERSION
     );
+    if let Ok(previous) = fs::read_to_string(&version_file) {
+        if previous.trim() != VERSION {
+            println!("  Upgrading from version {}", previous.trim());
+        }
+    }
+
+    let changes = upgrade::plan(
+        &current_dir,
+        manifest,
+        reference_planned_files(&current_dir),
+        &released_files(&[], &current_dir),
+        manifest::REFERENCE_OWNER,
+        options.force,
+        &format!("cdd {}", VERSION),
+    );
+    upgrade::print_plan(&current_dir, &changes);
+
+    if options.dry_run {
+        return Ok(());
+    }
 
     // Create .context directory if it doesn't exist
     fs::create_dir_all(&context_path)?;
+    upgrade::apply(&current_dir, manifest, &changes)?;
 
-    // Extract only _reference from embedded .context (overwrites existing)
-    let reference_path = context_path.join("_reference");
-    extract_reference_from_embedded(&reference_path)?;
-
-    // Create .context/tasks directory for task files (if doesn't exist)
-    let tasks_path = context_path.join("tasks");
-    fs::create_dir_all(&tasks_path)?;
+    // Create the tasks directory for task files (if doesn't exist)
+    fs::create_dir_all(config.tasks_dir(&current_dir))?;
 
     // Write version file
     fs::write(&version_file, VERSION)?;
     println!("✓ Extracted .context/_reference files");
 
+    let conflicts = upgrade::conflict_count(&changes);
+    if conflicts > 0 {
+        println!(
+            "⚠️  {} file(s) have merge conflicts; resolve them before committing",
+            conflicts
+        );
+    }
+
     Ok(())
 }
 
-fn extract_reference_from_embedded(target_path: &Path) -> std::io::Result<()> {
-    // Extract only rules and templates from _reference, skip commands
-    extract_dir_selective(&REFERENCE_DIR, target_path, &["rules", "templates"])?;
-    Ok(())
+// Files extracted into .context/_reference: rules and templates, commands
+// go to the agent profiles instead
+fn reference_planned_files(root: &Path) -> Vec<PlannedFile> {
+    planned_files_from(&REFERENCE_DIR, root)
+}
+
+// The rules and templates in an embedded _reference directory, as installed
+// into the project
+fn planned_files_from(reference: &Dir, root: &Path) -> Vec<PlannedFile> {
+    let target_path = root.join(".context/_reference");
+    let mut files = Vec::new();
+
+    for dir_name in ["rules", "templates"] {
+        if let Some(dir) = reference.get_dir(reference.path().join(dir_name)) {
+            collect_embedded_files(dir, reference.path(), &target_path, &mut files);
+        }
+    }
+
+    files
 }
 
-fn extract_dir_selective(
+fn collect_embedded_files(
     dir: &Dir,
+    prefix: &Path,
     target_path: &Path,
-    allowed_dirs: &[&str],
-) -> std::io::Result<()> {
-    // Create the target directory
-    fs::create_dir_all(target_path)?;
-
-    // Extract all files (only files directly in this directory, not subdirectories)
+    files: &mut Vec<PlannedFile>,
+) {
+    // Embedded paths are relative to the embedded root (e.g. "rules/rs.md"
+    // or "0.1.7/rules/rs.md")
     for file in dir.files() {
-        // Get just the filename, not the full path
-        let file_name = file.path().file_name().ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid file name")
-        })?;
-        let file_path = target_path.join(file_name);
-        fs::write(&file_path, file.contents())?;
+        let path = file.path().strip_prefix(prefix).unwrap_or(file.path());
+        files.push(PlannedFile {
+            path: target_path.join(path),
+            contents: String::from_utf8_lossy(file.contents()).to_string(),
+        });
     }
 
-    // Recursively extract subdirectories (only if in allowed list)
     for subdir in dir.dirs() {
-        // Get just the directory name, not the full path
-        let dir_name = subdir.path().file_name().ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid directory name")
-        })?;
-
-        let dir_name_str = dir_name.to_str().unwrap_or("");
+        collect_embedded_files(subdir, prefix, target_path, files);
+    }
+}
 
-        // Only extract if in allowed list
-        if allowed_dirs.contains(&dir_name_str) {
-            let subdir_path = target_path.join(dir_name);
-            extract_dir_all(subdir, &subdir_path)?;
-        }
+fn copy_commands(profile: &dyn AgentProfile, manifest: &mut Manifest) -> std::io::Result<()> {
+    let current_dir = env::current_dir()?;
+    let reference = embedded_reference();
+
+    // The profile decides where and in which format commands and rules are written
+    let (pending, changes): (Vec<_>, Vec<_>) = upgrade::plan(
+        &current_dir,
+        manifest,
+        profile.planned_files(&current_dir, &reference),
+        &released_files(&[profile], &current_dir),
+        profile.id(),
+        false,
+        &format!("cdd {}", VERSION),
+    )
+    .into_iter()
+    .partition(upgrade::needs_review);
+
+    // Files the user changed are left for `cdd update` to review
+    upgrade::apply(&current_dir, manifest, &changes)?;
+    for change in pending {
+        let path = &change.file.path;
+        println!(
+            "  • {} has local changes, run 'cdd update' to review",
+            path.strip_prefix(&current_dir).unwrap_or(path).display()
+        );
     }
 
-    Ok(())
+    profile.install(&current_dir, &reference)
 }
 
-fn extract_dir_all(dir: &Dir, target_path: &Path) -> std::io::Result<()> {
-    // Create the target directory
-    fs::create_dir_all(target_path)?;
-
-    // Extract all files
-    for file in dir.files() {
-        let file_name = file.path().file_name().ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid file name")
-        })?;
-        let file_path = target_path.join(file_name);
-        fs::write(&file_path, file.contents())?;
-    }
+fn embedded_reference() -> Reference<'static> {
+    reference_from(&REFERENCE_DIR)
+}
 
-    // Recursively extract all subdirectories
-    for subdir in dir.dirs() {
-        let dir_name = subdir.path().file_name().ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid directory name")
-        })?;
-        let subdir_path = target_path.join(dir_name);
-        extract_dir_all(subdir, &subdir_path)?;
+fn reference_from(reference: &'static Dir<'static>) -> Reference<'static> {
+    Reference {
+        commands: reference_files(reference, "commands"),
+        rules: reference_files(reference, "rules"),
     }
-
-    Ok(())
 }
 
-fn copy_commands(choice: &str) -> std::io::Result<()> {
-    let current_dir = env::current_dir()?;
+// The project's TASK.md template, which teams may customise, or the shipped one
+fn task_template(root: &Path) -> String {
+    fs::read_to_string(root.join(".context/_reference/templates/TASK.md")).unwrap_or_else(|_| {
+        REFERENCE_DIR
+            .get_file("templates/TASK.md")
+            .and_then(|file| file.contents_utf8())
+            .unwrap_or_default()
+            .to_string()
+    })
+}
 
-    // Determine target directory and command folder name
-    let (target_dir, command_folder) = match choice {
-        "Claude Code" => (current_dir.join(".claude"), "commands"), // plural
-        "OpenCode" => (current_dir.join(".opencode"), "command"),   // singular
-        _ => unreachable!(),
+// Collect the embedded files directly inside _reference/<subdir>
+fn reference_files(reference: &'static Dir<'static>, subdir: &str) -> Vec<ReferenceFile<'static>> {
+    let Some(dir) = reference.get_dir(reference.path().join(subdir)) else {
+        return Vec::new();
     };
 
-    // Create target directory if it doesn't exist
-    if !target_dir.exists() {
-        fs::create_dir(&target_dir)?;
+    dir.files()
+        .filter_map(|file| {
+            let name = file.path().file_name()?.to_str()?;
+            let contents = file.contents_utf8()?;
+            Some(ReferenceFile { name, contents })
+        })
+        .collect()
+}
+
+// Everything this version of cdd installs, with the owner recorded in the
+// manifest; stands in for the manifest of older installs
+fn shipped_files(registry: &ProfileRegistry, root: &Path) -> Vec<(String, PlannedFile)> {
+    let reference = embedded_reference();
+    let mut files: Vec<(String, PlannedFile)> = reference_planned_files(root)
+        .into_iter()
+        .map(|file| (manifest::REFERENCE_OWNER.to_string(), file))
+        .collect();
+
+    for profile in registry.trusted() {
+        files.extend(
+            profile
+                .planned_files(root, &reference)
+                .into_iter()
+                .map(|file| (profile.id().to_string(), file)),
+        );
+    }
+
+    // An untouched copy of an earlier release counts as shipped too
+    let profiles: Vec<&dyn AgentProfile> = registry.trusted().collect();
+    for earlier in released_files(&profiles, root) {
+        if fs::read(&earlier.path).ok().as_deref() != Some(earlier.contents.as_bytes()) {
+            continue;
+        }
+        if let Some((_, file)) = files.iter_mut().find(|(_, file)| file.path == earlier.path) {
+            *file = earlier;
+        }
     }
 
-    // Copy commands from embedded REFERENCE_DIR to .claude/commands or .opencode/command
-    if let Some(commands_dir) = REFERENCE_DIR.get_dir("commands") {
-        let target_commands_dir = target_dir.join(command_folder);
-        fs::create_dir_all(&target_commands_dir)?;
+    files
+}
 
-        // Copy all command files
-        for file in commands_dir.files() {
-            let file_name = file.path().file_name().ok_or_else(|| {
-                std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid file name")
-            })?;
-            let target_path = target_commands_dir.join(file_name);
+// What earlier releases installed for `profiles`. The release
+// recorded in .context/.version comes last, as the likeliest merge base for
+// files edited before cdd kept base copies.
+fn released_files(profiles: &[&dyn AgentProfile], root: &Path) -> Vec<PlannedFile> {
+    let recorded = fs::read_to_string(root.join(".context/.version")).unwrap_or_default();
+    let mut releases: Vec<&'static Dir<'static>> = RELEASED_DIR.dirs().collect();
+    releases.sort_by_key(|release| {
+        let version = release.path().to_string_lossy().to_string();
+        let numbers: Vec<u64> = version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        (version == recorded.trim(), numbers)
+    });
 
-            // Only copy if file doesn't exist (don't overwrite user's custom commands)
-            if !target_path.exists() {
-                fs::write(&target_path, file.contents())?;
-            }
+    let mut files = Vec::new();
+    for release in releases {
+        let reference = reference_from(release);
+        files.extend(planned_files_from(release, root));
+        for profile in profiles {
+            files.extend(profile.planned_files(root, &reference));
         }
     }
-
-    Ok(())
+    files
 }
 
-fn run_task() -> std::io::Result<()> {
+fn run_task(
+    registry: &ProfileRegistry,
+    config: &Config,
+    agent: Option<&dyn AgentProfile>,
+    set_default: bool,
+    mut filters: picker::Filters,
+    target: Option<&str>,
+    select_one: bool,
+) -> std::io::Result<()> {
     let current_dir = env::current_dir()?;
-    let tasks_dir = current_dir.join(".context/tasks");
+    let tasks_dir = config.tasks_dir(&current_dir);
 
     // Check if tasks directory exists
     if !tasks_dir.exists() {
-        eprintln!("Error: .context/tasks/ directory not found.");
+        eprintln!("Error: {}/ directory not found.", config.tasks.dir);
         eprintln!("Run 'cdd install' first to initialize the project.");
         process::exit(1);
     }
 
-    // Detect which profile is set up
-    let claude_exists = current_dir.join(".claude/commands").exists();
-    let opencode_exists = current_dir.join(".opencode/command").exists();
+    if migrate::has_pending(&current_dir, VERSION) {
+        println!("Note: .context uses an older layout. Run 'cdd migrate' to update it.");
+    }
 
-    let (profile_name, command_name) = if opencode_exists {
-        ("OpenCode", "opencode")
-    } else if claude_exists {
-        ("Claude Code", "claude")
-    } else {
-        eprintln!("Error: Neither .claude/commands nor .opencode/command found.");
-        eprintln!("Run 'cdd install' first to initialize the project.");
-        process::exit(1);
-    };
+    let mut task_files = Vec::new();
+    files::collect_files(&tasks_dir, &mut task_files)?;
+    if !task_files
+        .iter()
+        .any(|path| path.extension().is_some_and(|ext| ext == "md"))
+    {
+        if let Some(target) = target {
+            eprintln!("Error: no task matches '{}'", target);
+            process::exit(EXIT_NOT_FOUND);
+        }
+        println!("No tasks found in {}/", config.tasks.dir);
+        println!("Tasks will appear here after you create them.");
+        return Ok(());
+    }
 
-    // Collect all task files and directories
-    // Format: (display_name, path, is_directory)
-    let mut task_items: Vec<(String, std::path::PathBuf, bool)> = Vec::new();
-
-    // Recursively collect both directories and files
-    fn collect_items(
-        dir: &std::path::Path,
-        base: &std::path::Path,
-        items: &mut Vec<(String, std::path::PathBuf, bool)>,
-    ) -> std::io::Result<()> {
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                // Add the directory itself as a selectable item
-                if let Ok(rel_path) = path.strip_prefix(base) {
-                    let display_name = format!("📁 {}/", rel_path.display());
-                    items.push((display_name, path.clone(), true));
-                }
-                // Recurse into subdirectories
-                collect_items(&path, base, items)?;
-            } else if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
-                // Add markdown files
-                if let Ok(rel_path) = path.strip_prefix(base) {
-                    let display_name = rel_path.display().to_string();
-                    items.push((display_name, path, false));
+    // A target that names one task skips the picker
+    let mut query = String::new();
+    let mut found = None;
+    if let Some(target) = target {
+        let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+        match picker::resolve(
+            &current_dir,
+            &tasks_dir,
+            &filters,
+            target,
+            select_one,
+            interactive,
+        )? {
+            Resolution::Found(selections) => found = Some(selections),
+            Resolution::Query => query = target.to_string(),
+            Resolution::NotFound => {
+                eprintln!("Error: no task matches '{}'", target);
+                process::exit(EXIT_NOT_FOUND);
+            }
+            Resolution::Ambiguous(paths) => {
+                eprintln!("Error: '{}' matches {} tasks:", target, paths.len());
+                for path in &paths {
+                    eprintln!("    {}", files::relative(&current_dir, path));
                 }
+                eprintln!("Use a task id or path to pick one.");
+                process::exit(EXIT_AMBIGUOUS);
             }
         }
-        Ok(())
     }
 
-    collect_items(&tasks_dir, &tasks_dir, &mut task_items)?;
+    // Actions other than launching come back to the picker
+    let selections = if let Some(selections) = found {
+        selections
+    } else {
+        loop {
+            let Some((action, selections)) =
+                picker::pick(config, &current_dir, &tasks_dir, &mut filters, &mut query)?
+            else {
+                println!("Selection cancelled.");
+                return Ok(());
+            };
+
+            match action {
+                Action::Launch => break selections,
+                Action::PrintPath => {
+                    for selection in &selections {
+                        println!("{}", files::relative(&current_dir, &selection.path));
+                    }
+                    return Ok(());
+                }
+                action => {
+                    for selection in &selections {
+                        if selection.is_dir {
+                            println!(
+                                "Skipped {}/: folders can only be launched.",
+                                files::relative(&tasks_dir, &selection.path)
+                            );
+                        } else if let Err(e) =
+                            task_action(&current_dir, config, action, &selection.path)
+                        {
+                            eprintln!("Warning: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+    };
 
-    if task_items.is_empty() {
-        println!("No tasks found in .context/tasks/");
-        println!("Tasks will appear here after you create them.");
-        return Ok(());
+    // Only pick the agent once there is a task to give it
+    let profile = select_agent(registry, config, &current_dir, agent);
+    // A command chosen by the repository needs the user's go-ahead
+    if let Some(file) = profile.project_file() {
+        if !profiles::trust(&current_dir, file)? {
+            eprintln!(
+                "Error: not launching the untrusted profile {}",
+                files::relative(&current_dir, file)
+            );
+            eprintln!("Run cdd in a terminal to review it, or pick another agent with --agent.");
+            process::exit(1);
+        }
     }
 
-    // Sort: directories first, then files, both alphabetically
-    task_items.sort_by(|a, b| {
-        match (a.2, b.2) {
-            (true, false) => std::cmp::Ordering::Less, // directories before files
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.0.cmp(&b.0), // alphabetical within same type
+    if set_default {
+        config::set_default_agent(profile.id())?;
+        println!("✓ Default agent set to {}", profile.name());
+    }
+
+    // Tasks that become in-progress once the agent is running
+    let mut starting: Vec<Task> = Vec::new();
+    let (task_content, display_info, diff, task) = match selections.as_slice() {
+        [selection] if !selection.is_dir => {
+            // Single file selected
+            let content = fs::read_to_string(&selection.path)?;
+            match Task::parse(&selection.path, &content) {
+                Ok(task) => {
+                    starting.push(task.clone());
+                    let info = match &task.title {
+                        Some(title) => format!("{}: {}", task.id(), title),
+                        None => task.id(),
+                    };
+                    let diff = task.file_system_diff();
+                    (content, info, diff, Some(task))
+                }
+                Err(e) => {
+                    // Still launch, the agent can work with a malformed file
+                    eprintln!("Warning: {}", e);
+                    let diff = file_system_diff::parse_file_system_diff(&content);
+                    let info = files::relative(&tasks_dir, &selection.path);
+                    (content, info, diff, None)
+                }
+            }
         }
-    });
+        _ => {
+            // A folder or several marked entries - one prompt with every task
+            let groups: Vec<Vec<std::path::PathBuf>> = selections
+                .iter()
+                .map(|selection| selection.tasks.clone())
+                .collect();
+            let prompt_bytes = |content: &str| {
+                let subject = prompt::Subject {
+                    task: None,
+                    content,
+                };
+                prompt::render(&current_dir, config, profile.id(), &subject)
+                    .map_or(content.len(), |prompt| prompt.len())
+            };
+            let bundle = bundle::bundle(
+                &current_dir,
+                &groups,
+                config.run.token_budget,
+                &prompt_bytes,
+            )?;
+            let budget = match config.run.token_budget {
+                0 => "no budget".to_string(),
+                budget => format!("budget {}", bundle::approx(budget)),
+            };
+            println!(
+                "📦 Bundled {} of {} tasks, ~{} tokens ({})",
+                bundle.included,
+                bundle.tasks,
+                bundle::approx(bundle.tokens),
+                budget
+            );
+            for line in &bundle.report {
+                println!("   {}", line);
+            }
+            let combined_content = bundle.content;
 
-    // Create input for skim (just the display names)
-    let display_names: Vec<String> = task_items.iter().map(|(name, _, _)| name.clone()).collect();
-    let input = display_names.join("\n");
+            // Tasks marked one by one are being started; a folder's are not
+            for selection in selections.iter().filter(|selection| !selection.is_dir) {
+                let content = fs::read_to_string(&selection.path)?;
+                if let Ok(task) = Task::parse(&selection.path, &content) {
+                    starting.push(task);
+                }
+            }
 
-    // Get absolute path to tasks directory for preview
-    let tasks_dir_abs = tasks_dir.canonicalize().unwrap_or(tasks_dir.clone());
-    let task_path_template = tasks_dir_abs.join("{}").display().to_string();
+            let info = match selections.as_slice() {
+                [folder] => format!(
+                    "{}/ ({} tasks)",
+                    files::relative(&tasks_dir, &folder.path),
+                    bundle.tasks
+                ),
+                _ => format!("{} tasks", bundle.tasks),
+            };
+            let diff = file_system_diff::parse_file_system_diff(&combined_content);
+            (combined_content, info, diff, None)
+        }
+    };
 
-    // Platform-specific preview command
-    #[cfg(windows)]
-    let preview_cmd = format!("type {}", task_path_template);
+    // Render the prompt template for this agent and task
+    let subject = prompt::Subject {
+        task: task.as_ref(),
+        content: &task_content,
+    };
+    let prompt = prompt::render(&current_dir, config, profile.id(), &subject)?;
+    if prompt.len() > prompt::MAX_BYTES {
+        eprintln!(
+            "Error: the prompt is {} KiB, more than the 128 KiB an agent takes on its command line",
+            prompt.len().div_ceil(1024)
+        );
+        eprintln!("Run `cdd prompt` to see it, then shorten the task, its rules or the template.");
+        process::exit(1);
+    }
+    let scoped: Vec<String> = prompt::scoped_rules(&current_dir, &task_content)
+        .into_iter()
+        .map(|rule| rule.name)
+        .collect();
 
-    #[cfg(not(windows))]
-    let preview_cmd = format!(
-        "bat --color=always --style=plain {} 2>/dev/null || cat {}",
-        task_path_template, task_path_template
+    // Launch the appropriate tool with prompt
+    println!(
+        "\n🚀 Launching {} with task: {}",
+        profile.name(),
+        display_info
     );
+    if !scoped.is_empty() {
+        println!("📏 Rules for the files it touches: {}", scoped.join(", "));
+    }
+    println!();
 
-    // Configure skim options with preview
-    let options = SkimOptionsBuilder::default()
-        .height(Some("50%"))
-        .multi(false)
-        .preview(Some(&preview_cmd))
-        .preview_window(Some("right:60%:wrap"))
-        .prompt(Some("Select a task: "))
-        .build()
-        .unwrap();
-
-    // Run skim
-    let item_reader = SkimItemReader::default();
-    let items = item_reader.of_bufread(Cursor::new(input));
-
-    let selected_items = Skim::run_with(&options, Some(items));
-
-    match selected_items {
-        Some(out) if !out.is_abort => {
-            if let Some(item) = out.selected_items.first() {
-                let selected_display_name = item.output().to_string();
-
-                // Find the corresponding item
-                let selected_item = task_items
-                    .iter()
-                    .find(|(name, _, _)| name == &selected_display_name);
-
-                let (task_content, display_info) = if let Some((_, path, is_dir)) = selected_item {
-                    if *is_dir {
-                        // Directory selected - load ALL tasks in this directory
-                        let mut combined_content = String::new();
-                        let mut task_count = 0;
-
-                        // Collect all markdown files in this directory recursively
-                        fn collect_dir_tasks(
-                            dir: &std::path::Path,
-                            content: &mut String,
-                            count: &mut usize,
-                        ) -> std::io::Result<()> {
-                            for entry in fs::read_dir(dir)? {
-                                let entry = entry?;
-                                let entry_path = entry.path();
-                                if entry_path.is_dir() {
-                                    collect_dir_tasks(&entry_path, content, count)?;
-                                } else if entry_path.is_file()
-                                    && entry_path.extension().is_some_and(|ext| ext == "md")
-                                {
-                                    if let Ok(file_content) = fs::read_to_string(&entry_path) {
-                                        if *count > 0 {
-                                            content.push_str("\n\n---\n\n");
-                                        }
-                                        content.push_str(&format!(
-                                            "## File: {}\n\n{}",
-                                            entry_path.display(),
-                                            file_content
-                                        ));
-                                        *count += 1;
-                                    }
-                                }
-                            }
-                            Ok(())
-                        }
-
-                        if let Err(e) =
-                            collect_dir_tasks(path, &mut combined_content, &mut task_count)
-                        {
-                            eprintln!("Error reading directory tasks: {}", e);
-                            process::exit(1);
-                        }
-
-                        let info = format!("{} ({} tasks)", selected_display_name, task_count);
-                        (combined_content, info)
-                    } else {
-                        // Single file selected
-                        match fs::read_to_string(path) {
-                            Ok(content) => (content, selected_display_name.clone()),
-                            Err(e) => {
-                                eprintln!("Error reading task file: {}", e);
-                                process::exit(1);
-                            }
-                        }
-                    }
-                } else {
-                    eprintln!("Error: Selected item not found");
-                    process::exit(1);
-                };
+    // Files the task expects to touch, for agents that take them up front
+    let files: Vec<std::path::PathBuf> = file_system_diff::touched_files(&diff)
+        .into_iter()
+        .map(std::path::PathBuf::from)
+        .collect();
 
-                // Create the prompt with task content
-                let prompt = format!("I want to work on this task:\n\n{}", task_content);
+    // Each profile knows how to invoke its agent
+    let mut cmd = profile.launch(&Launch {
+        prompt: &prompt,
+        files: &files,
+    });
+    let command_name = cmd.get_program().to_string_lossy().to_string();
+    let status = cmd.current_dir(&current_dir).spawn().and_then(|mut child| {
+        for task in &starting {
+            if let Err(e) = status::start_on_launch(task) {
+                eprintln!("Warning: could not update status: {}", e);
+            }
+        }
+        child.wait()
+    });
 
-                // Launch the appropriate tool with prompt
-                println!(
-                    "\n🚀 Launching {} with task: {}",
-                    profile_name, display_info
+    match status {
+        Ok(exit_status) => {
+            if !exit_status.success() {
+                eprintln!(
+                    "Warning: {} exited with status: {}",
+                    command_name, exit_status
                 );
-                println!();
-
-                // Different invocation for opencode vs claude
-                let status = if command_name == "opencode" {
-                    // Try to use user's last used model from OpenCode
-                    let mut cmd = process::Command::new(command_name);
-                    cmd.current_dir(&current_dir);
-
-                    if let Some(model) = get_opencode_last_model() {
-                        cmd.arg("--model").arg(&model);
-                    }
-
-                    cmd.arg("--prompt").arg(&prompt).status()
-                } else {
-                    // claude just takes the prompt as an argument
-                    process::Command::new(command_name)
-                        .current_dir(&current_dir)
-                        .arg(&prompt)
-                        .status()
-                };
-
-                match status {
-                    Ok(exit_status) => {
-                        if !exit_status.success() {
-                            eprintln!(
-                                "Warning: {} exited with status: {}",
-                                command_name, exit_status
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Error launching {}: {}", command_name, e);
-                        eprintln!("Make sure {} is installed and in your PATH.", command_name);
-                        process::exit(1);
-                    }
-                }
             }
         }
-        _ => {
-            println!("Selection cancelled.");
+        Err(e) => {
+            eprintln!("Error launching {}: {}", command_name, e);
+            eprintln!("Make sure {} is installed and in your PATH.", command_name);
+            process::exit(1);
         }
     }
 
     Ok(())
 }
 
-fn get_opencode_last_model() -> Option<String> {
-    // OpenCode stores recent models in ~/.local/state/opencode/model.json
-    let state_file = dirs::home_dir()?.join(".local/state/opencode/model.json");
+// Edit, mark done, archive or rename the task at `path` from the picker
+fn task_action(root: &Path, config: &Config, action: Action, path: &Path) -> std::io::Result<()> {
+    match action {
+        Action::Edit => actions::edit(root, path),
+        Action::Archive => actions::archive(root, config, path).map(|_| ()),
+        Action::Done | Action::Rename => {
+            let content = fs::read_to_string(path)?;
+            let task = Task::parse(path, &content)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+            if action == Action::Done {
+                status::complete(root, &task, false)
+            } else {
+                actions::rename(root, config, &task).map(|_| ())
+            }
+        }
+        Action::Launch | Action::PrintPath => Ok(()),
+    }
+}
 
-    if !state_file.exists() {
-        return None;
+// Pick the agent to launch: --agent first, then the user's default, then the
+// only installed profile, otherwise ask when there is a terminal
+fn select_agent<'a>(
+    registry: &'a ProfileRegistry,
+    config: &Config,
+    root: &Path,
+    requested: Option<&'a dyn AgentProfile>,
+) -> &'a dyn AgentProfile {
+    let installed = registry.installed(root, &config.agents.installed);
+
+    if let Some(profile) = requested {
+        if !installed.iter().any(|p| p.id() == profile.id()) {
+            eprintln!(
+                "Error: {} is not installed in this project.",
+                profile.name()
+            );
+            eprintln!("Run 'cdd install -p {}' first.", profile.id());
+            process::exit(1);
+        }
+        return profile;
     }
 
-    // Read and parse the JSON file
-    let content = fs::read_to_string(&state_file).ok()?;
-    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    if installed.is_empty() {
+        eprintln!(
+            "Error: No agent profile installed (checked: {}).",
+            registry.ids().join(", ")
+        );
+        eprintln!("Run 'cdd install' first to initialize the project.");
+        process::exit(1);
+    }
 
-    // Get the first item from the "recent" array
-    let recent = json.get("recent")?.as_array()?;
-    let last_model = recent.first()?;
+    if let Some(profile) = installed.iter().find(|p| p.matches(&config.agents.default)) {
+        return *profile;
+    }
 
-    // Extract providerID and modelID
-    let provider_id = last_model.get("providerID")?.as_str()?;
-    let model_id = last_model.get("modelID")?.as_str()?;
+    if installed.len() == 1 {
+        return installed[0];
+    }
 
-    Some(format!("{}/{}", provider_id, model_id))
+    // Scripts can't answer the prompt, so they have to say which agent
+    if !(std::io::stdin().is_terminal() && std::io::stdout().is_terminal()) {
+        let ids: Vec<&str> = installed.iter().map(|p| p.id()).collect();
+        eprintln!(
+            "Error: {} agents are installed ({}) and no default is set.",
+            installed.len(),
+            ids.join(", ")
+        );
+        eprintln!(
+            "Pass --agent <ID>, or set a default with 'cdd config set agents.default <ID> --user'."
+        );
+        process::exit(1);
+    }
+
+    let options: Vec<&str> = installed.iter().map(|p| p.name()).collect();
+    match Select::new("Choose an agent:", options)
+        .with_help_message("Use --set-default to skip this next time")
+        .prompt()
+    {
+        Ok(name) => registry.find(name).expect("selected profile is registered"),
+        Err(_) => {
+            println!("Selection cancelled.");
+            process::exit(0);
+        }
+    }
 }
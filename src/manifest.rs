@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Record of every file `cdd install` wrote, with the hash of what it wrote,
+// so uninstall and upgrades can tell cdd's files from the user's edits
+const MANIFEST_FILE: &str = ".context/.manifest.toml";
+
+// Copies of the contents cdd shipped, used as the common ancestor when
+// merging upgrades into files the user edited
+const BASE_DIR: &str = ".context/.base";
+
+// Owner of the files extracted into .context/_reference
+pub const REFERENCE_OWNER: &str = "reference";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ManifestEntry {
+    // Relative to the project root, or absolute for files outside it
+    pub path: String,
+    pub sha256: String,
+    // "reference" for .context/_reference, otherwise the profile id
+    pub owner: String,
+}
+
+pub fn manifest_path(root: &Path) -> PathBuf {
+    root.join(MANIFEST_FILE)
+}
+
+pub fn sha256_hex(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+// Hash of a file on disk, or None if it can't be read
+pub fn file_hash(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|contents| sha256_hex(&contents))
+}
+
+pub fn base_dir(root: &Path) -> PathBuf {
+    root.join(BASE_DIR)
+}
+
+// Where the shipped copy of `path` is kept; None for files outside the project
+pub fn base_path(root: &Path, path: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(root).ok()?;
+    Some(base_dir(root).join(relative))
+}
+
+pub fn read_base(root: &Path, path: &Path) -> Option<String> {
+    fs::read_to_string(base_path(root, path)?).ok()
+}
+
+pub fn write_base(root: &Path, path: &Path, contents: &str) -> io::Result<()> {
+    let Some(base) = base_path(root, path) else {
+        return Ok(());
+    };
+    if let Some(parent) = base.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(base, contents)
+}
+
+impl Manifest {
+    // None when the project was installed before manifests existed
+    pub fn load(root: &Path) -> io::Result<Option<Manifest>> {
+        let content = match fs::read_to_string(manifest_path(root)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        toml::from_str(&content).map(Some).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", MANIFEST_FILE, e),
+            )
+        })
+    }
+
+    pub fn save(&self, root: &Path) -> io::Result<()> {
+        let content = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        fs::write(manifest_path(root), content)
+    }
+
+    pub fn entry(&self, root: &Path, path: &Path) -> Option<&ManifestEntry> {
+        let key = manifest_key(root, path);
+        self.files.iter().find(|entry| entry.path == key)
+    }
+
+    // Add or update the entry for a file cdd just wrote
+    pub fn record(&mut self, root: &Path, path: &Path, contents: &[u8], owner: &str) {
+        let key = manifest_key(root, path);
+        let entry = ManifestEntry {
+            path: key.clone(),
+            sha256: sha256_hex(contents),
+            owner: owner.to_string(),
+        };
+
+        match self.files.iter_mut().find(|existing| existing.path == key) {
+            Some(existing) => *existing = entry,
+            None => self.files.push(entry),
+        }
+    }
+
+    // True if the file still has exactly the contents cdd wrote
+    pub fn is_unmodified(&self, root: &Path, path: &Path) -> bool {
+        self.entry(root, path)
+            .is_some_and(|entry| file_hash(path).as_deref() == Some(entry.sha256.as_str()))
+    }
+}
+
+impl ManifestEntry {
+    pub fn absolute_path(&self, root: &Path) -> PathBuf {
+        root.join(&self.path)
+    }
+
+    pub fn is_inside_project(&self) -> bool {
+        Path::new(&self.path).is_relative()
+    }
+}
+
+fn manifest_key(root: &Path, path: &Path) -> String {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return path.display().to_string();
+    };
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,304 @@
+// Line-based three-way merge (diff3 style) and unified diffs, used when
+// upgrading files the user has edited since cdd installed them
+
+// Lines of context around each change in a unified diff
+const DIFF_CONTEXT: usize = 3;
+
+pub struct MergeResult {
+    pub text: String,
+    pub conflicts: usize,
+}
+
+// Merge the changes from `base` to `ours` with the changes from `base` to
+// `theirs`. Regions both sides changed differently are wrapped in conflict
+// markers labelled with `ours_label` and `theirs_label`.
+pub fn merge3(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    ours_label: &str,
+    theirs_label: &str,
+) -> MergeResult {
+    // Lines are compared with their newline, so merge as if every side ended
+    // with one and merge the missing final newline like any other change
+    let ends_with_newline = |text: &str| text.is_empty() || text.ends_with('\n');
+    let keep_final_newline = if ends_with_newline(ours) == ends_with_newline(base) {
+        ends_with_newline(theirs)
+    } else {
+        ends_with_newline(ours)
+    };
+    let (base, ours, theirs) = (
+        with_final_newline(base),
+        with_final_newline(ours),
+        with_final_newline(theirs),
+    );
+
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let our_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let their_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    let our_matches = matching_lines(&base_lines, &our_lines);
+    let their_matches = matching_lines(&base_lines, &their_lines);
+
+    let mut text = String::new();
+    let mut conflicts = 0;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+
+    loop {
+        // Next base line kept unchanged on both sides
+        let stable = (i..base_lines.len()).find_map(|index| {
+            match (our_matches[index], their_matches[index]) {
+                (Some(a), Some(b)) if a >= j && b >= k => Some((index, a, b)),
+                _ => None,
+            }
+        });
+
+        let (end_base, end_ours, end_theirs) = match stable {
+            Some(found) => found,
+            None => (base_lines.len(), our_lines.len(), their_lines.len()),
+        };
+
+        let chunk_base = &base_lines[i..end_base];
+        let chunk_ours = &our_lines[j..end_ours];
+        let chunk_theirs = &their_lines[k..end_theirs];
+
+        if chunk_ours == chunk_base || chunk_ours == chunk_theirs {
+            text.extend(chunk_theirs.iter().copied());
+        } else if chunk_theirs == chunk_base {
+            text.extend(chunk_ours.iter().copied());
+        } else {
+            conflicts += 1;
+            push_conflict(
+                &mut text,
+                chunk_ours,
+                chunk_theirs,
+                ours_label,
+                theirs_label,
+            );
+        }
+
+        match stable {
+            Some((index, a, b)) => {
+                text.push_str(base_lines[index]);
+                i = index + 1;
+                j = a + 1;
+                k = b + 1;
+            }
+            None => break,
+        }
+    }
+
+    // A conflict at the end keeps its marker line whole
+    if !keep_final_newline && !text.ends_with(&format!(">>>>>>> {}\n", theirs_label)) {
+        text.pop();
+    }
+    MergeResult { text, conflicts }
+}
+
+fn with_final_newline(text: &str) -> String {
+    if text.is_empty() || text.ends_with('\n') {
+        text.to_string()
+    } else {
+        format!("{}\n", text)
+    }
+}
+
+fn push_conflict(
+    text: &mut String,
+    ours: &[&str],
+    theirs: &[&str],
+    ours_label: &str,
+    theirs_label: &str,
+) {
+    text.push_str(&format!("<<<<<<< {}\n", ours_label));
+    text.extend(ours.iter().copied());
+    text.push_str("=======\n");
+    text.extend(theirs.iter().copied());
+    text.push_str(&format!(">>>>>>> {}\n", theirs_label));
+}
+
+// Unified diff from `old` to `new`, empty when they are equal
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+
+    // Edit script as (marker, line) pairs
+    let mut ops: Vec<(char, &str)> = Vec::new();
+    let mut y = 0;
+    for (x, matched) in matching_lines(&old_lines, &new_lines)
+        .into_iter()
+        .enumerate()
+    {
+        match matched {
+            Some(target) => {
+                ops.extend(new_lines[y..target].iter().map(|line| ('+', *line)));
+                ops.push((' ', old_lines[x]));
+                y = target + 1;
+            }
+            None => ops.push(('-', old_lines[x])),
+        }
+    }
+    ops.extend(new_lines[y..].iter().map(|line| ('+', *line)));
+
+    let changed: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != ' ').collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", old_label, new_label);
+
+    // Changes closer than twice the context share a hunk
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for &index in &changed {
+        match groups.last_mut() {
+            Some((_, last)) if index - *last <= 2 * DIFF_CONTEXT => *last = index,
+            _ => groups.push((index, index)),
+        }
+    }
+
+    for (first, last) in groups {
+        let start = first.saturating_sub(DIFF_CONTEXT);
+        let end = (last + 1 + DIFF_CONTEXT).min(ops.len());
+
+        let old_start = ops[..start].iter().filter(|(op, _)| *op != '+').count();
+        let new_start = ops[..start].iter().filter(|(op, _)| *op != '-').count();
+        let old_len = ops[start..end].iter().filter(|(op, _)| *op != '+').count();
+        let new_len = ops[start..end].iter().filter(|(op, _)| *op != '-').count();
+
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start + 1,
+            old_len,
+            new_start + 1,
+            new_len
+        ));
+        for (op, line) in &ops[start..end] {
+            diff.push(*op);
+            diff.push_str(line);
+            if !line.ends_with('\n') {
+                diff.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    diff
+}
+
+// For each line of `base`, the index of the matching line in `other` along
+// the longest common subsequence
+fn matching_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let (n, m) = (base.len(), other.len());
+
+    // lengths[x][y] = LCS length of base[x..] and other[y..]
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for x in (0..n).rev() {
+        for y in (0..m).rev() {
+            lengths[x][y] = if base[x] == other[y] {
+                lengths[x + 1][y + 1] + 1
+            } else {
+                lengths[x + 1][y].max(lengths[x][y + 1])
+            };
+        }
+    }
+
+    let mut matches = vec![None; n];
+    let (mut x, mut y) = (0, 0);
+    while x < n && y < m {
+        if base[x] == other[y] {
+            matches[x] = Some(y);
+            x += 1;
+            y += 1;
+        } else if lengths[x + 1][y] >= lengths[x][y + 1] {
+            x += 1;
+        } else {
+            y += 1;
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(base: &str, ours: &str, theirs: &str) -> MergeResult {
+        merge3(base, ours, theirs, "local", "cdd")
+    }
+
+    #[test]
+    fn merges_changes_to_different_lines() {
+        let result = merge("a\nb\nc\nd\n", "A\nb\nc\nd\n", "a\nb\nc\nD\n");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.text, "A\nb\nc\nD\n");
+    }
+
+    #[test]
+    fn keeps_one_copy_of_the_same_change() {
+        let result = merge("a\nb\n", "a\nB\n", "a\nB\n");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.text, "a\nB\n");
+    }
+
+    #[test]
+    fn marks_lines_both_sides_changed() {
+        let result = merge("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.text,
+            "a\n<<<<<<< local\nours\n=======\ntheirs\n>>>>>>> cdd\nc\n"
+        );
+    }
+
+    #[test]
+    fn markers_start_on_their_own_line_without_a_trailing_newline() {
+        let result = merge("a\nb", "a\nours", "a\ntheirs");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.text,
+            "a\n<<<<<<< local\nours\n=======\ntheirs\n>>>>>>> cdd\n"
+        );
+    }
+
+    #[test]
+    fn keeps_a_missing_trailing_newline_from_one_side() {
+        let result = merge("a\nb\nc\n", "A\nb\nc\n", "a\nb\nc");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.text, "A\nb\nc");
+    }
+
+    #[test]
+    fn matches_a_last_line_the_other_side_added_a_newline_to() {
+        let result = merge("a\nb", "A\nb", "a\nb\nc\n");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.text, "A\nb\nc\n");
+    }
+
+    #[test]
+    fn deleting_a_line_the_other_side_edited_conflicts() {
+        let result = merge("a\nb\nc\n", "a\nc\n", "a\nB\nc\n");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.text,
+            "a\n<<<<<<< local\n=======\nB\n>>>>>>> cdd\nc\n"
+        );
+    }
+
+    #[test]
+    fn deleting_an_untouched_line_merges() {
+        let result = merge("a\nb\nc\nd\n", "a\nc\nd\n", "a\nb\nc\nD\n");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.text, "a\nc\nD\n");
+    }
+
+    #[test]
+    fn diff_of_equal_texts_is_empty() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+    }
+
+    #[test]
+    fn diff_shows_changed_lines_with_context() {
+        let diff = unified_diff("a\nb\nc\n", "a\nB\nc\n", "old", "new");
+        assert_eq!(diff, "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+    }
+}
@@ -0,0 +1,827 @@
+use crate::config::Config;
+use crate::files::{self, collect_files, relative, remove_empty_parents};
+use crate::manifest::sha256_hex;
+use crate::task;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+// Snapshots taken before migrating, each with a journal of what the
+// migration did; `cdd migrate --rollback` undoes the newest
+const SNAPSHOT_DIR: &str = ".context/.snapshots";
+
+// Changes to the .context layout, applied in order to projects whose
+// .context/.version is older than `version`. Every step only acts on what
+// is still in the old shape, so running a migration twice is harmless.
+struct Migration {
+    version: &'static str,
+    description: &'static str,
+    steps: &'static [Step],
+}
+
+enum Step {
+    // Move the files of a directory (relative to the project root)
+    MoveDir {
+        from: &'static str,
+        to: &'static str,
+    },
+    // Move project folders holding task files from .context/ into the tasks directory
+    CollectTaskFolders,
+    // Rename a frontmatter key in every task file
+    RenameFrontmatterKey {
+        from: &'static str,
+        to: &'static str,
+    },
+    // TASK_001_add_auth.md -> TASK_001-add-auth.md
+    NormalizeTaskFileNames,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: "0.1.7",
+    description: "Move the context_driven_development layout to _reference/ and tasks/",
+    steps: &[
+        Step::MoveDir {
+            from: ".context/context_driven_development/templates",
+            to: ".context/_reference/templates",
+        },
+        Step::MoveDir {
+            from: ".context/context_driven_development/rules",
+            to: ".context/_reference/rules",
+        },
+        Step::CollectTaskFolders,
+        Step::RenameFrontmatterKey {
+            from: "task-id",
+            to: "task_id",
+        },
+        Step::RenameFrontmatterKey {
+            from: "taskId",
+            to: "task_id",
+        },
+        Step::NormalizeTaskFileNames,
+    ],
+}];
+
+// A single file system change planned by a step
+enum Operation {
+    Move { from: PathBuf, to: PathBuf },
+    // Source already exists at the destination with the same contents
+    RemoveDuplicate { path: PathBuf, kept: PathBuf },
+    // Source and destination differ; left for the user to sort out
+    Conflict { from: PathBuf, to: PathBuf },
+    Rewrite { path: PathBuf, contents: String },
+}
+
+// What a migration did, saved next to its snapshot as it goes, so a rollback
+// undoes exactly that and leaves later work alone. Paths are relative to the
+// project root.
+#[derive(Default, Serialize, Deserialize)]
+struct Journal {
+    // .context/.version before migrating, None if there was no file
+    version: Option<String>,
+    done: Vec<Done>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Done {
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    // The file's contents are in the snapshot
+    Remove {
+        path: PathBuf,
+    },
+    // The hash is of what was written, to tell whether it was edited since
+    Rewrite {
+        path: PathBuf,
+        before: String,
+        sha256: String,
+    },
+}
+
+impl Done {
+    fn of(root: &Path, tree: &Tree, operation: &Operation) -> Option<Done> {
+        let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_path_buf();
+        match operation {
+            Operation::Move { from, to } => Some(Done::Move {
+                from: relative(from),
+                to: relative(to),
+            }),
+            Operation::RemoveDuplicate { path, .. } => Some(Done::Remove {
+                path: relative(path),
+            }),
+            Operation::Conflict { .. } => None,
+            Operation::Rewrite { path, contents } => Some(Done::Rewrite {
+                path: relative(path),
+                before: String::from_utf8_lossy(tree.read(path).unwrap_or_default()).to_string(),
+                sha256: sha256_hex(contents.as_bytes()),
+            }),
+        }
+    }
+}
+
+// The files under .context and the tasks directory, held in memory so each
+// step is planned against what the steps before it left, in dry runs too
+struct Tree {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl Tree {
+    // Everything under `roots` except the snapshots
+    fn load(root: &Path, roots: &[PathBuf]) -> io::Result<Tree> {
+        let mut paths = Vec::new();
+        for path in roots {
+            collect_files(path, &mut paths)?;
+        }
+        let mut files = BTreeMap::new();
+        for path in paths
+            .into_iter()
+            .filter(|path| !path.starts_with(root.join(SNAPSHOT_DIR)))
+        {
+            let contents = fs::read(&path)?;
+            files.insert(path, contents);
+        }
+        Ok(Tree { files })
+    }
+
+    // Files below `dir`, sorted
+    fn files_under(&self, dir: &Path) -> Vec<PathBuf> {
+        self.files
+            .keys()
+            .filter(|path| path.starts_with(dir) && *path != dir)
+            .cloned()
+            .collect()
+    }
+
+    // Directories directly inside `dir` that hold files
+    fn dirs_in(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for path in self.files.keys() {
+            let Ok(rest) = path.strip_prefix(dir) else {
+                continue;
+            };
+            let mut components = rest.components();
+            if let (Some(first), Some(_)) = (components.next(), components.next()) {
+                let child = dir.join(first);
+                if !dirs.contains(&child) {
+                    dirs.push(child);
+                }
+            }
+        }
+        dirs
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path) || !self.files_under(path).is_empty()
+    }
+
+    fn read(&self, path: &Path) -> Option<&[u8]> {
+        self.files.get(path).map(Vec::as_slice)
+    }
+
+    fn apply(&mut self, operation: &Operation) {
+        match operation {
+            Operation::Move { from, to } => {
+                if let Some(contents) = self.files.remove(from) {
+                    self.files.insert(to.clone(), contents);
+                }
+            }
+            Operation::RemoveDuplicate { path, .. } => {
+                self.files.remove(path);
+            }
+            Operation::Conflict { .. } => {}
+            Operation::Rewrite { path, contents } => {
+                self.files
+                    .insert(path.clone(), contents.clone().into_bytes());
+            }
+        }
+    }
+}
+
+// Migrations newer than the project and not newer than this binary
+fn pending(project_version: &str, binary_version: &str) -> Vec<&'static Migration> {
+    MIGRATIONS
+        .iter()
+        .filter(|migration| {
+            parse_version(migration.version) > parse_version(project_version)
+                && parse_version(migration.version) <= parse_version(binary_version)
+        })
+        .collect()
+}
+
+// Version recorded in .context/.version; projects from before the file
+// existed count as 0.0.0
+pub fn project_version(root: &Path) -> Option<String> {
+    if !root.join(".context").is_dir() {
+        return None;
+    }
+    let version = fs::read_to_string(root.join(".context/.version")).unwrap_or_default();
+    let version = version.trim();
+    Some(if version.is_empty() { "0.0.0" } else { version }.to_string())
+}
+
+pub fn has_pending(root: &Path, binary_version: &str) -> bool {
+    project_version(root).is_some_and(|version| !pending(&version, binary_version).is_empty())
+}
+
+// `cdd migrate [--dry-run]`: print the planned operations and, unless it is
+// a dry run, snapshot .context and apply them
+pub fn migrate(
+    root: &Path,
+    config: &Config,
+    binary_version: &str,
+    dry_run: bool,
+) -> io::Result<()> {
+    let Some(version) = project_version(root) else {
+        println!("No .context directory found, nothing to migrate.");
+        return Ok(());
+    };
+
+    let migrations = pending(&version, binary_version);
+    if migrations.is_empty() {
+        println!("✓ .context layout is up to date (version {})", version);
+        return Ok(());
+    }
+
+    println!(
+        "🔧 Migrating .context from version {} to {}{}",
+        version,
+        binary_version,
+        if dry_run { " (dry run)" } else { "" }
+    );
+
+    let mut journal = None;
+    if !dry_run {
+        let snapshot = write_snapshot(root, config)?;
+        println!(
+            "  📦 Snapshot written to {} (undo with 'cdd migrate --rollback')",
+            relative(root, &snapshot)
+        );
+        let started = Journal {
+            version: fs::read_to_string(root.join(".context/.version")).ok(),
+            done: Vec::new(),
+        };
+        journal = Some((snapshot.with_extension("json"), started));
+    }
+
+    let tasks_dir = config.tasks_dir(root);
+    let mut tree = Tree::load(root, &snapshot_roots(root, config))?;
+    for migration in migrations {
+        println!("\n{}: {}", migration.version, migration.description);
+
+        let mut changed = false;
+        for step in migration.steps {
+            let operations = plan_step(root, &tasks_dir, &tree, step);
+            for operation in &operations {
+                print_operation(root, operation);
+                if let Some((path, journal)) = &mut journal {
+                    apply_operation(root, operation)?;
+                    if let Some(done) = Done::of(root, &tree, operation) {
+                        journal.done.push(done);
+                        save_journal(path, journal)?;
+                    }
+                }
+                tree.apply(operation);
+            }
+            changed |= !operations.is_empty();
+        }
+        if !changed {
+            println!("  (nothing to change)");
+        }
+
+        if !dry_run {
+            fs::write(root.join(".context/.version"), migration.version)?;
+        }
+    }
+
+    if dry_run {
+        println!("\nDry run: nothing was written.");
+    } else {
+        println!("\n✅ Migration complete.");
+    }
+    Ok(())
+}
+
+fn plan_step(root: &Path, tasks_dir: &Path, tree: &Tree, step: &Step) -> Vec<Operation> {
+    let mut operations = Vec::new();
+
+    match step {
+        Step::MoveDir { from, to } => {
+            plan_move(tree, &root.join(from), &root.join(to), &mut operations);
+        }
+        Step::CollectTaskFolders => {
+            for path in tree.dirs_in(&root.join(".context")) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let reserved = name.starts_with('.')
+                    || name.starts_with('_')
+                    || ["tasks", "profiles", "context_driven_development"].contains(&&*name);
+                let has_task = tree
+                    .files_under(&path)
+                    .iter()
+                    .any(|file| is_task_file(file));
+                if reserved || path == tasks_dir || !has_task {
+                    continue;
+                }
+                plan_move(tree, &path, &tasks_dir.join(&*name), &mut operations);
+            }
+        }
+        Step::RenameFrontmatterKey { from, to } => {
+            for path in task_files(tree, tasks_dir) {
+                let content = String::from_utf8_lossy(tree.read(&path).unwrap_or_default());
+                if let Some(contents) = rename_frontmatter_key(&content, from, to) {
+                    operations.push(Operation::Rewrite { path, contents });
+                }
+            }
+        }
+        Step::NormalizeTaskFileNames => {
+            for path in task_files(tree, tasks_dir) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if let Some(normalized) = normalize_task_file_name(&name) {
+                    let to = path.with_file_name(normalized);
+                    operations.push(if tree.exists(&to) {
+                        Operation::Conflict { from: path, to }
+                    } else {
+                        Operation::Move { from: path, to }
+                    });
+                }
+            }
+        }
+    }
+
+    operations
+}
+
+// File by file, so a partially migrated directory can be finished later
+fn plan_move(tree: &Tree, from: &Path, to: &Path, operations: &mut Vec<Operation>) {
+    for file in tree.files_under(from) {
+        let target = to.join(file.strip_prefix(from).unwrap_or(&file));
+        let operation = if !tree.exists(&target) {
+            Operation::Move {
+                from: file,
+                to: target,
+            }
+        } else if tree.read(&file) == tree.read(&target) {
+            Operation::RemoveDuplicate {
+                path: file,
+                kept: target,
+            }
+        } else {
+            Operation::Conflict {
+                from: file,
+                to: target,
+            }
+        };
+        operations.push(operation);
+    }
+}
+
+fn print_operation(root: &Path, operation: &Operation) {
+    match operation {
+        Operation::Move { from, to } => {
+            println!("  → {} → {}", relative(root, from), relative(root, to))
+        }
+        Operation::RemoveDuplicate { path, kept } => println!(
+            "  - {} (same as {})",
+            relative(root, path),
+            relative(root, kept)
+        ),
+        Operation::Conflict { from, to } => println!(
+            "  ! {} left in place: {} already exists with different content",
+            relative(root, from),
+            relative(root, to)
+        ),
+        Operation::Rewrite { path, .. } => {
+            println!("  ~ {} (frontmatter)", relative(root, path))
+        }
+    }
+}
+
+fn apply_operation(root: &Path, operation: &Operation) -> io::Result<()> {
+    match operation {
+        Operation::Move { from, to } => {
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(from, to)?;
+            remove_empty_parents(root, from);
+        }
+        Operation::RemoveDuplicate { path, .. } => {
+            fs::remove_file(path)?;
+            remove_empty_parents(root, path);
+        }
+        Operation::Conflict { .. } => {}
+        Operation::Rewrite { path, contents } => fs::write(path, contents)?,
+    }
+    Ok(())
+}
+
+// `cdd migrate --rollback`: undo what the latest migration did. Every undo
+// is checked first; if a file it touches changed since, nothing is undone.
+pub fn rollback(root: &Path) -> io::Result<()> {
+    let Some(snapshot) = latest_snapshot(root)? else {
+        println!("No migration snapshot found in {}/", SNAPSHOT_DIR);
+        return Ok(());
+    };
+    let journal_path = snapshot.with_extension("json");
+    let journal: Journal = match fs::read_to_string(&journal_path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", relative(root, &journal_path), e),
+            )
+        })?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{} has no record of what the migration changed; restore files from it by hand",
+                    relative(root, &snapshot)
+                ),
+            ));
+        }
+        Err(e) => return Err(e),
+    };
+    let originals = read_snapshot(&snapshot)?;
+
+    let problems = check_undo(root, &journal, &originals);
+    if !problems.is_empty() {
+        eprintln!("These files changed after the migration:");
+        for problem in &problems {
+            eprintln!("  ! {}", problem);
+        }
+        return Err(io::Error::other(format!(
+            "not rolling back, nothing was changed; {} still holds the files from before",
+            relative(root, &snapshot)
+        )));
+    }
+
+    for done in journal.done.iter().rev() {
+        match done {
+            Done::Move { from, to } => {
+                let (from, to) = (root.join(from), root.join(to));
+                if let Some(parent) = from.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(&to, &from)?;
+                remove_empty_parents(root, &to);
+                println!("  ← {} → {}", relative(root, &to), relative(root, &from));
+            }
+            Done::Remove { path } => {
+                let target = root.join(path);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&target, &originals[path])?;
+                println!("  ← {} (restored)", path.display());
+            }
+            Done::Rewrite { path, before, .. } => {
+                fs::write(root.join(path), before)?;
+                println!("  ← {} (frontmatter restored)", path.display());
+            }
+        }
+    }
+
+    let version_file = root.join(".context/.version");
+    match &journal.version {
+        Some(version) => fs::write(&version_file, version)?,
+        None => fs::remove_file(&version_file)?,
+    }
+    fs::remove_file(&journal_path)?;
+    fs::remove_file(&snapshot)?;
+    remove_empty_parents(root, &snapshot);
+    println!(
+        "✓ Undid {} change(s) from {}",
+        journal.done.len(),
+        relative(root, &snapshot)
+    );
+    Ok(())
+}
+
+// Walk the undos backwards against where each file is now, describing any
+// that would lose work
+fn check_undo(
+    root: &Path,
+    journal: &Journal,
+    originals: &BTreeMap<PathBuf, Vec<u8>>,
+) -> Vec<String> {
+    // Where the content of a path is on disk once the later undos are done
+    let mut moved: BTreeMap<PathBuf, Option<PathBuf>> = BTreeMap::new();
+    let location =
+        |moved: &BTreeMap<PathBuf, Option<PathBuf>>, path: &PathBuf| match moved.get(path) {
+            Some(location) => location.clone(),
+            None => root.join(path).exists().then(|| path.clone()),
+        };
+
+    let mut problems = Vec::new();
+    for done in journal.done.iter().rev() {
+        match done {
+            Done::Move { from, to } => {
+                let Some(current) = location(&moved, to) else {
+                    problems.push(format!("{} was moved or deleted", to.display()));
+                    continue;
+                };
+                if location(&moved, from).is_some() {
+                    problems.push(format!("{} exists again", from.display()));
+                    continue;
+                }
+                moved.insert(from.clone(), Some(current));
+                moved.insert(to.clone(), None);
+            }
+            Done::Remove { path } => {
+                if location(&moved, path).is_some() {
+                    problems.push(format!("{} exists again", path.display()));
+                } else if !originals.contains_key(path) {
+                    problems.push(format!("{} is missing from the snapshot", path.display()));
+                }
+                moved.insert(path.clone(), Some(path.clone()));
+            }
+            Done::Rewrite { path, sha256, .. } => match location(&moved, path) {
+                Some(current) => {
+                    let written = fs::read(root.join(&current)).map(|c| sha256_hex(&c));
+                    if written.ok().as_ref() != Some(sha256) {
+                        problems.push(format!("{} was edited", current.display()));
+                    }
+                }
+                None => problems.push(format!("{} was moved or deleted", path.display())),
+            },
+        }
+    }
+    problems
+}
+
+fn save_journal(path: &Path, journal: &Journal) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(journal).map_err(io::Error::other)?;
+    fs::write(path, json)
+}
+
+// The snapshot's files by path relative to the project root
+fn read_snapshot(snapshot: &Path) -> io::Result<BTreeMap<PathBuf, Vec<u8>>> {
+    let mut files = BTreeMap::new();
+    let mut archive = tar::Archive::new(fs::File::open(snapshot)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        files.insert(path, contents);
+    }
+    Ok(files)
+}
+
+fn latest_snapshot(root: &Path) -> io::Result<Option<PathBuf>> {
+    let dir = root.join(SNAPSHOT_DIR);
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+
+    // Timestamped names sort chronologically
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "tar") {
+            snapshots.push(path);
+        }
+    }
+    snapshots.sort();
+    Ok(snapshots.pop())
+}
+
+// .context, plus the tasks directory when it lives elsewhere
+fn snapshot_roots(root: &Path, config: &Config) -> Vec<PathBuf> {
+    let context_path = root.join(".context");
+    let tasks_dir = config.tasks_dir(root);
+    let mut roots = vec![context_path.clone()];
+    if !tasks_dir.starts_with(&context_path) {
+        roots.push(tasks_dir);
+    }
+    roots
+}
+
+fn write_snapshot(root: &Path, config: &Config) -> io::Result<PathBuf> {
+    let snapshot_dir = root.join(SNAPSHOT_DIR);
+    fs::create_dir_all(&snapshot_dir)?;
+    let snapshot = snapshot_dir.join(format!("migrate-{}.tar", files::timestamp()));
+
+    let mut files = Vec::new();
+    for path in snapshot_roots(root, config) {
+        collect_files(&path, &mut files)?;
+    }
+
+    files.retain(|file| !file.starts_with(&snapshot_dir));
+    files::write_tar(root, &snapshot, &files)?;
+
+    Ok(snapshot)
+}
+
+fn task_files(tree: &Tree, tasks_dir: &Path) -> Vec<PathBuf> {
+    let mut files = tree.files_under(tasks_dir);
+    files.retain(|file| is_task_file(file));
+    files
+}
+
+fn is_task_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.starts_with("TASK_") && name.ends_with(".md")
+}
+
+// None when the key isn't in the frontmatter (or the new key already is)
+fn rename_frontmatter_key(content: &str, from: &str, to: &str) -> Option<String> {
+    let (frontmatter, body) = task::split_frontmatter(content);
+    let frontmatter = frontmatter?;
+
+    let has_key = |key: &str| {
+        frontmatter
+            .lines()
+            .any(|line| line.starts_with(&format!("{}:", key)))
+    };
+    if !has_key(from) || has_key(to) {
+        return None;
+    }
+
+    let renamed: String = frontmatter
+        .lines()
+        .map(|line| match line.strip_prefix(&format!("{}:", from)) {
+            Some(value) => format!("{}:{}\n", to, value),
+            None => format!("{}\n", line),
+        })
+        .collect();
+
+    Some(format!("---\n{}---\n{}", renamed, body))
+}
+
+fn normalize_task_file_name(name: &str) -> Option<String> {
+    let rest = name.strip_prefix("TASK_")?.strip_suffix(".md")?;
+    let digits_end = rest.find(|c: char| !c.is_ascii_digit())?;
+    let (number, slug) = rest.split_at(digits_end);
+    let slug = slug.strip_prefix('_')?;
+    if number.is_empty() || slug.is_empty() {
+        return None;
+    }
+    Some(format!("TASK_{}-{}.md", number, slug.replace('_', "-")))
+}
+
+// "0.1.7" -> [0, 1, 7]; anything unparsable counts as 0
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .split('.')
+        .map(|part| {
+            part.chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>()
+                .parse()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A project as cdd 0.1.6 and earlier laid it out
+    fn old_layout(root: &Path) {
+        let files = [
+            (
+                ".context/context_driven_development/templates/TASK.md",
+                "---\ntask_id: TASK_XXX\n---\n",
+            ),
+            (
+                ".context/context_driven_development/rules/rs.md",
+                "# Rust\n",
+            ),
+            (
+                ".context/auth/TASK_001_add_auth.md",
+                "---\ntask-id: TASK_001\ntitle: Add auth\n---\n\n# TASK_001: Add auth\n",
+            ),
+            (
+                ".context/auth/TASK_002-login.md",
+                "---\ntaskId: TASK_002\ntitle: Login\n---\n",
+            ),
+            (".context/auth/notes.md", "Notes\n"),
+        ];
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    // Every file under `root` with its contents
+    fn snapshot(root: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        let mut paths = Vec::new();
+        collect_files(root, &mut paths).unwrap();
+        paths
+            .into_iter()
+            .map(|path| {
+                let contents = fs::read(&path).unwrap();
+                (path.strip_prefix(root).unwrap().to_path_buf(), contents)
+            })
+            .collect()
+    }
+
+    fn migrated(root: &Path) {
+        old_layout(root);
+        migrate(root, &Config::default(), "0.1.7", false).unwrap();
+    }
+
+    #[test]
+    fn migration_moves_renames_and_rewrites() {
+        let dir = tempfile::tempdir().unwrap();
+        migrated(dir.path());
+
+        let files = snapshot(dir.path());
+        let task = &files[Path::new(".context/tasks/auth/TASK_001-add-auth.md")];
+        assert!(String::from_utf8_lossy(task).starts_with("---\ntask_id: TASK_001\n"));
+        assert!(files.contains_key(Path::new(".context/tasks/auth/TASK_002-login.md")));
+        assert!(files.contains_key(Path::new(".context/tasks/auth/notes.md")));
+        assert!(files.contains_key(Path::new(".context/_reference/rules/rs.md")));
+        assert!(files.contains_key(Path::new(".context/_reference/templates/TASK.md")));
+        assert_eq!(
+            fs::read_to_string(dir.path().join(".context/.version")).unwrap(),
+            "0.1.7"
+        );
+    }
+
+    #[test]
+    fn rollback_restores_the_tree_byte_for_byte() {
+        let dir = tempfile::tempdir().unwrap();
+        old_layout(dir.path());
+        let before = snapshot(dir.path());
+
+        migrate(dir.path(), &Config::default(), "0.1.7", false).unwrap();
+        assert_ne!(snapshot(dir.path()), before);
+        rollback(dir.path()).unwrap();
+
+        assert_eq!(snapshot(dir.path()), before);
+    }
+
+    #[test]
+    fn rollback_keeps_tasks_created_after_the_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        migrated(dir.path());
+        let new_task = dir.path().join(".context/tasks/auth/TASK_003-logout.md");
+        fs::write(&new_task, "---\ntask_id: TASK_003\n---\n").unwrap();
+
+        rollback(dir.path()).unwrap();
+
+        assert!(new_task.exists());
+        assert!(dir
+            .path()
+            .join(".context/auth/TASK_001_add_auth.md")
+            .exists());
+    }
+
+    #[test]
+    fn rollback_refuses_over_an_edited_file_and_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        migrated(dir.path());
+        let task = dir.path().join(".context/tasks/auth/TASK_001-add-auth.md");
+        fs::write(&task, "---\ntask_id: TASK_001\ntitle: Edited\n---\n").unwrap();
+        let edited = snapshot(dir.path());
+
+        let error = rollback(dir.path()).unwrap_err();
+
+        assert!(error.to_string().contains("not rolling back"));
+        assert_eq!(snapshot(dir.path()), edited);
+    }
+
+    #[test]
+    fn rollback_refuses_when_a_moved_file_is_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        migrated(dir.path());
+        fs::remove_file(dir.path().join(".context/tasks/auth/notes.md")).unwrap();
+        let changed = snapshot(dir.path());
+
+        assert!(rollback(dir.path()).is_err());
+        assert_eq!(snapshot(dir.path()), changed);
+    }
+
+    #[test]
+    fn every_step_leaves_a_migrated_tree_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        migrated(root);
+
+        let config = Config::default();
+        let tree = Tree::load(root, &snapshot_roots(root, &config)).unwrap();
+        let tasks_dir = config.tasks_dir(root);
+        for step in MIGRATIONS[0].steps {
+            assert!(plan_step(root, &tasks_dir, &tree, step).is_empty());
+        }
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        old_layout(dir.path());
+        let before = snapshot(dir.path());
+
+        migrate(dir.path(), &Config::default(), "0.1.7", true).unwrap();
+
+        assert_eq!(snapshot(dir.path()), before);
+    }
+}
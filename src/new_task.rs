@@ -0,0 +1,515 @@
+// `cdd new`: write a task file from the TASK.md template without an agent.
+// Anything not given as a flag is asked for, using the question format from
+// Appendix A of the shipped commands (numbered questions, lettered options
+// with a recommended one, or a short custom answer).
+
+use crate::config::Config;
+use crate::files::relative;
+use crate::ids;
+use crate::profiles::yaml_string;
+use crate::task::{self, MAX_GOALS};
+use chrono::NaiveDate;
+use inquire::{Select, Text};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// Longest file name slug, cut at a word boundary
+const MAX_SLUG_LEN: usize = 50;
+
+const SHORT_ANSWER: &str = "Short | Provide a different answer (≤5 words)";
+
+#[derive(Default)]
+struct NewTask {
+    title: Option<String>,
+    project: Option<String>,
+    folder: Option<String>,
+    summary: Option<String>,
+    goals: Vec<String>,
+    no_input: bool,
+}
+
+pub fn new_task(root: &Path, config: &Config, template: &str, args: &[String]) -> io::Result<()> {
+    let mut new = parse_args(args);
+    let tasks_dir = config.tasks_dir(root);
+    fs::create_dir_all(&tasks_dir)?;
+
+    let interactive = !new.no_input;
+    let total = 5;
+
+    if new.title.is_none() {
+        if !interactive {
+            eprintln!("Error: --title is required with --no-input");
+            process::exit(1);
+        }
+        let title = Text::new(&format!("Question [1/{}]: What is the task title?", total))
+            .with_help_message("The file name is made from the title")
+            .with_validator(|value: &str| {
+                Ok(if value.trim().is_empty() {
+                    inquire::validator::Validation::Invalid("A title is required".into())
+                } else {
+                    inquire::validator::Validation::Valid
+                })
+            })
+            .prompt()
+            .map_err(cancelled)?;
+        new.title = Some(title.trim().to_string());
+    }
+    let title = new.title.clone().unwrap_or_default();
+
+    let project = match new.project.clone() {
+        Some(project) => project,
+        None => {
+            let (options, recommended, reason) = project_options(root, &tasks_dir)?;
+            if interactive {
+                ask(
+                    &format!("Question [2/{}]: Which project does this belong to?", total),
+                    &options,
+                    recommended,
+                    reason,
+                )?
+            } else {
+                options[recommended].clone()
+            }
+        }
+    };
+
+    let folder = match new.folder.clone() {
+        Some(folder) => folder,
+        None => {
+            let (options, recommended) = folder_options(&tasks_dir, &project)?;
+            if interactive && options.len() > 1 {
+                let labels: Vec<String> = options
+                    .iter()
+                    .map(|folder| folder_label(&tasks_dir, root, folder))
+                    .collect();
+                let answer = ask(
+                    &format!("Question [3/{}]: Which folder should it go in?", total),
+                    &labels,
+                    recommended,
+                    "Tasks of this project already live there",
+                )?;
+                match labels.iter().position(|label| *label == answer) {
+                    Some(index) => options[index].clone(),
+                    None => answer,
+                }
+            } else {
+                options[recommended].clone()
+            }
+        }
+    };
+
+    if new.summary.is_none() && interactive {
+        let summary = Text::new(&format!(
+            "Question [4/{}]: Summarize what this task accomplishes and why it matters:",
+            total
+        ))
+        .with_help_message("One paragraph; press Enter to fill it in later")
+        .prompt()
+        .map_err(cancelled)?;
+        if !summary.trim().is_empty() {
+            new.summary = Some(summary.trim().to_string());
+        }
+    }
+
+    if new.goals.is_empty() && interactive {
+        while new.goals.len() < MAX_GOALS {
+            let goal = Text::new(&format!(
+                "Question [5/{}]: Goal {} (a specific, measurable outcome):",
+                total,
+                new.goals.len() + 1
+            ))
+            .with_help_message("Aim for 3 goals; press Enter when done")
+            .prompt()
+            .map_err(cancelled)?;
+            if goal.trim().is_empty() {
+                break;
+            }
+            new.goals.push(goal.trim().to_string());
+        }
+    }
+
+    let id = ids::allocate(config.id_strategy(), &tasks_dir, &project, &title, &[])?;
+    let created = chrono::Local::now().date_naive();
+    let project_root = root
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string());
+    let content = render(
+        template,
+        &id,
+        &title,
+        &project,
+        &new,
+        created,
+        &project_root,
+    );
+
+    let dir = tasks_dir.join(&folder);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}-{}.md", id, slugify(&title)));
+    if path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", relative(root, &path)),
+        ));
+    }
+    fs::write(&path, content)?;
+
+    println!("✓ Created {}", relative(root, &path));
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> NewTask {
+    let mut new = NewTask::default();
+    let mut i = 0;
+
+    while i < args.len() {
+        let flag = args[i].as_str();
+        if flag == "--no-input" {
+            new.no_input = true;
+            i += 1;
+            continue;
+        }
+
+        let Some(value) = args.get(i + 1).cloned() else {
+            eprintln!("Error: {} requires a value", flag);
+            print_usage();
+            process::exit(1);
+        };
+        match flag {
+            "--title" | "-t" => new.title = Some(value),
+            "--project" => new.project = Some(value),
+            "--folder" => new.folder = Some(value),
+            "--summary" => new.summary = Some(value),
+            "--goal" => new.goals.push(value),
+            _ => {
+                eprintln!("Unknown option for 'cdd new': {}", flag);
+                print_usage();
+                process::exit(1);
+            }
+        }
+        i += 2;
+    }
+
+    new
+}
+
+fn print_usage() {
+    eprintln!(
+        "Usage: cdd new [--title <TITLE>] [--project <PROJECT>] [--folder <DIR>] [--summary <TEXT>] [--goal <GOAL>]... [--no-input]"
+    );
+}
+
+// An Appendix A question: lettered options starting on the recommended one,
+// plus a short custom answer
+fn ask(question: &str, options: &[String], recommended: usize, reason: &str) -> io::Result<String> {
+    let letters = ('A'..='Z').map(|letter| letter.to_string());
+    let mut labels: Vec<String> = letters
+        .zip(options)
+        .map(|(letter, option)| format!("{} | {}", letter, option))
+        .collect();
+    labels.push(SHORT_ANSWER.to_string());
+
+    let recommended_label = labels[recommended].clone();
+    let answer = Select::new(question, labels.clone())
+        .with_starting_cursor(recommended)
+        .with_help_message(&format!(
+            "Recommended: {} - {}",
+            recommended_label.split(" | ").next().unwrap_or_default(),
+            reason
+        ))
+        .prompt()
+        .map_err(cancelled)?;
+
+    if answer == SHORT_ANSWER {
+        let custom = Text::new("Your answer:").prompt().map_err(cancelled)?;
+        return Ok(custom.trim().to_string());
+    }
+
+    let index = labels
+        .iter()
+        .position(|label| *label == answer)
+        .unwrap_or(0);
+    Ok(options[index].clone())
+}
+
+pub fn cancelled(e: inquire::InquireError) -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, e.to_string())
+}
+
+// Projects used by existing tasks, most common first; without any, the
+// repository name as the commands suggest
+fn project_options(
+    root: &Path,
+    tasks_dir: &Path,
+) -> io::Result<(Vec<String>, usize, &'static str)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for task in task::load_all(tasks_dir)? {
+        let Some(project) = task.project else {
+            continue;
+        };
+        match counts.iter_mut().find(|(name, _)| *name == project) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((project, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    if counts.is_empty() {
+        let repo = root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_uppercase())
+            .unwrap_or_else(|| "MAIN".to_string());
+        let mut options = vec![repo];
+        if options[0] != "MAIN" {
+            options.push("MAIN".to_string());
+        }
+        return Ok((options, 0, "Named after the repository"));
+    }
+
+    let options = counts.into_iter().map(|(name, _)| name).collect();
+    Ok((options, 0, "Most existing tasks use it"))
+}
+
+// The tasks directory itself ("") and its subfolders; recommends a folder
+// already holding tasks of `project`, otherwise the top level
+fn folder_options(tasks_dir: &Path, project: &str) -> io::Result<(Vec<String>, usize)> {
+    let mut folders = vec![String::new()];
+    collect_folders(tasks_dir, tasks_dir, &mut folders)?;
+
+    let tasks = task::load_all(tasks_dir)?;
+    let recommended = tasks
+        .iter()
+        .filter(|task| task.project.as_deref() == Some(project))
+        .filter_map(|task| {
+            let parent = task.path.parent()?.strip_prefix(tasks_dir).ok()?;
+            let folder = parent.to_string_lossy().to_string();
+            folders.iter().position(|existing| *existing == folder)
+        })
+        .next()
+        .unwrap_or(0);
+
+    Ok((folders, recommended))
+}
+
+fn collect_folders(dir: &Path, base: &Path, folders: &mut Vec<String>) -> io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if let Ok(rel_path) = path.strip_prefix(base) {
+            folders.push(rel_path.to_string_lossy().to_string());
+        }
+        collect_folders(&path, base, folders)?;
+    }
+    Ok(())
+}
+
+fn folder_label(tasks_dir: &Path, root: &Path, folder: &str) -> String {
+    format!(
+        "{}/",
+        relative(root, &tasks_dir.join(folder)).trim_end_matches('/')
+    )
+}
+
+// "Add User Auth (v2)!" -> "add-user-auth-v2"
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let mut slug = slug.trim_end_matches('-').to_string();
+
+    if slug.len() > MAX_SLUG_LEN {
+        let cut = slug[..MAX_SLUG_LEN].rfind('-').unwrap_or(MAX_SLUG_LEN);
+        slug.truncate(cut);
+    }
+    if slug.is_empty() {
+        slug.push_str("task");
+    }
+    slug
+}
+
+// Fill the template: new frontmatter and heading, the given summary and
+// goals, an empty File System Diff tree and Lessons Learned, and the
+// template's concrete validation commands. Sections without an answer keep
+// the template's <placeholder> text.
+fn render(
+    template: &str,
+    id: &str,
+    title: &str,
+    project: &str,
+    new: &NewTask,
+    created: NaiveDate,
+    project_root: &str,
+) -> String {
+    // Without a summary and goals the task is only a draft
+    let status = if new.summary.is_some() && !new.goals.is_empty() {
+        "ready"
+    } else {
+        "draft"
+    };
+    let mut output = format!(
+        "---\ntask_id: {}\ntitle: {}\nproject: {}\nstatus: {}\ncreated: \"{}\"\n---\n",
+        id,
+        frontmatter_value(title),
+        frontmatter_value(project),
+        status,
+        created
+    );
+
+    let body = task::strip_comments(task::split_frontmatter(template).1);
+    let mut section: Option<String> = None;
+    let mut in_fence = false;
+
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            if section.as_deref() != Some("File System Diff") {
+                output.push_str(line);
+                output.push('\n');
+            }
+            continue;
+        }
+
+        if !in_fence {
+            if line.starts_with("# ") {
+                output.push_str(&format!("# {}: {}\n", id, title));
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("## ") {
+                section = Some(name.trim().to_string());
+                output.push_str(line);
+                output.push_str("\n\n");
+                output.push_str(&section_content(name.trim(), new, project_root));
+                continue;
+            }
+        }
+
+        let keep = match section.as_deref() {
+            // Placeholders stay only when there is nothing to put instead
+            Some("Summary") => new.summary.is_none(),
+            Some("Goals") => new.goals.is_empty(),
+            Some("File System Diff") => false,
+            Some("Lessons Learned") => false,
+            Some("Validation") => line.starts_with("- `") && !line.contains('<'),
+            _ => true,
+        };
+        if keep {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    // One blank line after the frontmatter and around headings; runs of
+    // blank lines elsewhere become one, so paragraphs stay apart
+    let mut tidy: Vec<&str> = Vec::new();
+    let mut rules = 0;
+    for line in output.lines() {
+        let after_blank = matches!(tidy.last(), None | Some(&""));
+        if line.trim().is_empty() {
+            if !after_blank {
+                tidy.push("");
+            }
+            continue;
+        }
+
+        let heading = line.starts_with('#');
+        if heading && !after_blank {
+            tidy.push("");
+        }
+        tidy.push(line);
+
+        if line == "---" {
+            rules += 1;
+        }
+        if heading || (line == "---" && rules == 2) {
+            tidy.push("");
+        }
+    }
+    format!("{}\n", tidy.join("\n").trim_end())
+}
+
+fn section_content(name: &str, new: &NewTask, project_root: &str) -> String {
+    match name {
+        "Summary" => new
+            .summary
+            .as_ref()
+            .map(|summary| format!("{}\n", summary))
+            .unwrap_or_default(),
+        "Goals" => new
+            .goals
+            .iter()
+            .map(|goal| format!("- {}\n", goal))
+            .collect(),
+        "File System Diff" => format!("```\n{}/\n```\n", project_root),
+        _ => String::new(),
+    }
+}
+
+// Plain values stay unquoted like in the template; anything YAML could
+// misread is quoted
+pub fn frontmatter_value(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || " _-.,()/'".contains(c))
+        && !value.starts_with(['-', ' ', '\''])
+        && !value.ends_with(' ');
+    if plain {
+        value.to_string()
+    } else {
+        yaml_string(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = include_str!("../.context/_reference/templates/TASK.md");
+
+    fn rendered(new: &NewTask) -> String {
+        let created = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        render(
+            TEMPLATE, "TASK_001", "Add auth", "core", new, created, "app",
+        )
+    }
+
+    #[test]
+    fn paragraphs_of_the_summary_stay_apart() {
+        let new = NewTask {
+            summary: Some("First paragraph.\n\n\nSecond paragraph.".to_string()),
+            goals: vec!["Log in".to_string()],
+            ..NewTask::default()
+        };
+        let output = rendered(&new);
+
+        assert!(
+            output.contains("## Summary\n\nFirst paragraph.\n\nSecond paragraph.\n\n## Goals\n")
+        );
+        assert!(!output.contains("\n\n\n"));
+    }
+
+    #[test]
+    fn one_blank_line_after_the_frontmatter_and_around_headings() {
+        let output = rendered(&NewTask::default());
+
+        assert!(output.starts_with(
+            "---\ntask_id: TASK_001\ntitle: Add auth\nproject: core\nstatus: draft\n\
+             created: \"2026-01-02\"\n---\n\n# TASK_001: Add auth\n\n## Summary\n\n<"
+        ));
+        assert!(output.contains("## File System Diff\n\n```\napp/\n```\n\n## Lessons Learned\n\n## Validation\n\n- `just dev`"));
+        assert!(!output.contains("\n\n\n"));
+        assert!(output.ends_with(")\n"));
+    }
+}
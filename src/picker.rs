@@ -0,0 +1,693 @@
+// The skim task picker behind `cdd run`: one line per task with ID, status,
+// project, title and age columns, filtered by status, project and tag.
+// Filters come from --status/--project/--tag/--all and can be changed with
+// key bindings, which re-open the picker with the same query. Other keys
+// pick the task for an action other than launching the agent. Tab marks
+// several entries to launch or act on together.
+
+use crate::config::Config;
+use crate::files::{collect_files, relative};
+use crate::preview::{self, FolderTask};
+use crate::status;
+use crate::task::Task;
+use chrono::NaiveDate;
+use skim::prelude::*;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// Statuses hidden unless asked for
+const FINISHED: &[&str] = &["done", "abandoned"];
+
+// Longest title shown before it is cut with "…"
+const MAX_TITLE_WIDTH: usize = 60;
+
+// Keys that change the filters instead of selecting
+const TOGGLE_ALL: &str = "alt-a";
+const CYCLE_STATUS: &str = "alt-s";
+const CYCLE_PROJECT: &str = "alt-p";
+const CYCLE_TAG: &str = "alt-t";
+
+// What to do with the picked task
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Launch,
+    Edit,
+    Done,
+    Archive,
+    Rename,
+    PrintPath,
+}
+
+const ACTION_KEYS: &[(&str, Action)] = &[
+    ("ctrl-e", Action::Edit),
+    ("ctrl-d", Action::Done),
+    ("ctrl-a", Action::Archive),
+    ("ctrl-r", Action::Rename),
+    ("ctrl-y", Action::PrintPath),
+];
+
+const KEY_HINTS: &str = "⏎ run ⇥ mark ^e edit ^d done ^a archive ^r rename ^y path";
+
+#[derive(Clone, Default)]
+pub struct Filters {
+    // Empty shows every status except done and abandoned
+    pub statuses: Vec<String>,
+    pub projects: Vec<String>,
+    pub tags: Vec<String>,
+    // Include done and abandoned tasks
+    pub all: bool,
+}
+
+// What was picked: a task file, or a folder standing for the visible tasks in it
+pub struct Selection {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub tasks: Vec<PathBuf>,
+}
+
+// A task file as the picker shows it
+struct Row {
+    path: PathBuf,
+    id: String,
+    // None when the status isn't one of the lifecycle states
+    status: Option<&'static str>,
+    status_text: String,
+    project: String,
+    title: String,
+    tags: Vec<String>,
+    date: Option<NaiveDate>,
+    // Files that fail to parse are listed with the problem as their title
+    broken: bool,
+}
+
+// A line in skim; its preview runs the configured command on the task file
+struct PickerItem {
+    label: String,
+    index: usize,
+    preview: Preview,
+    // Shared by every line of one picker run
+    marks: Arc<Mutex<Vec<usize>>>,
+}
+
+enum Preview {
+    Command(String),
+    // Rendered by crate::preview when the item is previewed
+    Task(PathBuf),
+    Text(String),
+}
+
+impl SkimItem for PickerItem {
+    fn text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.label)
+    }
+
+    fn preview(&self, context: PreviewContext) -> ItemPreview {
+        if let Ok(mut marks) = self.marks.lock() {
+            track_marks(&mut marks, context.selected_indices);
+        }
+        match &self.preview {
+            Preview::Command(command) => ItemPreview::Command(command.clone()),
+            Preview::Task(path) => ItemPreview::AnsiText(preview::task(path)),
+            Preview::Text(text) => ItemPreview::AnsiText(text.clone()),
+        }
+    }
+
+    fn output(&self) -> Cow<'_, str> {
+        Cow::Owned(self.index.to_string())
+    }
+}
+
+impl Filters {
+    fn matches(&self, row: &Row) -> bool {
+        let filtered =
+            !self.statuses.is_empty() || !self.projects.is_empty() || !self.tags.is_empty();
+        if row.broken {
+            return !filtered;
+        }
+
+        let status_ok = match row.status {
+            Some(status) if !self.statuses.is_empty() => {
+                self.statuses.iter().any(|wanted| wanted == status)
+            }
+            Some(status) => self.all || !FINISHED.contains(&status),
+            None => self.statuses.is_empty(),
+        };
+        let project_ok = self.projects.is_empty()
+            || self
+                .projects
+                .iter()
+                .any(|project| project.eq_ignore_ascii_case(&row.project));
+        let tag_ok = self.tags.is_empty()
+            || self
+                .tags
+                .iter()
+                .any(|tag| row.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)));
+
+        status_ok && project_ok && tag_ok
+    }
+
+    // One line describing the active filters
+    fn describe(&self, hidden: usize) -> String {
+        let list = |values: &[String], empty: &str| {
+            if values.is_empty() {
+                empty.to_string()
+            } else {
+                values.join(",")
+            }
+        };
+        let statuses = if self.statuses.is_empty() && !self.all {
+            "active".to_string()
+        } else {
+            list(&self.statuses, "any")
+        };
+
+        // Kept short: the picker only gets part of the terminal width
+        let mut line = format!(
+            "status={}  project={}  tag={}",
+            statuses,
+            list(&self.projects, "any"),
+            list(&self.tags, "any")
+        );
+        if hidden > 0 {
+            line.push_str(&format!("  ({} hidden)", hidden));
+        }
+        line
+    }
+}
+
+// How `cdd run TARGET` resolved its target
+pub enum Resolution {
+    // Launch these without showing the picker
+    Found(Vec<Selection>),
+    NotFound,
+    // Paths of the tasks the target could mean
+    Ambiguous(Vec<PathBuf>),
+    // Several tasks match: open the picker with the target as query
+    Query,
+}
+
+// Resolve TARGET as a path, then a task id from the frontmatter, then a
+// fuzzy query matched the way the picker would. A query matching a single
+// task is accepted with `select_one` or when nobody is there to pick, e.g.
+// in scripts and CI; otherwise several matches open the picker.
+pub fn resolve(
+    root: &Path,
+    tasks_dir: &Path,
+    filters: &Filters,
+    target: &str,
+    select_one: bool,
+    interactive: bool,
+) -> io::Result<Resolution> {
+    let rows = scan(tasks_dir)?;
+
+    let path = root.join(target);
+    if path.is_file() {
+        return Ok(Resolution::Found(vec![Selection {
+            tasks: vec![path.clone()],
+            path,
+            is_dir: false,
+        }]));
+    }
+    if path.is_dir() {
+        // Like a folder in the picker: the tasks in it the filters show
+        let tasks: Vec<PathBuf> = rows
+            .iter()
+            .filter(|row| row.path.starts_with(&path) && filters.matches(row))
+            .map(|row| row.path.clone())
+            .collect();
+        if tasks.is_empty() {
+            return Ok(Resolution::NotFound);
+        }
+        return Ok(Resolution::Found(vec![Selection {
+            path,
+            is_dir: true,
+            tasks,
+        }]));
+    }
+
+    // Ids match whatever the filters, so finished tasks can be re-run
+    let by_id: Vec<&Row> = rows
+        .iter()
+        .filter(|row| row.id.eq_ignore_ascii_case(target))
+        .collect();
+    match by_id.as_slice() {
+        [row] => {
+            return Ok(Resolution::Found(vec![Selection {
+                path: row.path.clone(),
+                is_dir: false,
+                tasks: vec![row.path.clone()],
+            }]))
+        }
+        [] => {}
+        rows => {
+            return Ok(Resolution::Ambiguous(
+                rows.iter().map(|row| row.path.clone()).collect(),
+            ))
+        }
+    }
+
+    let visible: Vec<&Row> = rows.iter().filter(|row| filters.matches(row)).collect();
+    let selections = entries(tasks_dir, &visible);
+    let (labels, _) = lines(tasks_dir, &visible, &selections);
+    let engine =
+        AndOrEngineFactory::new(ExactOrFuzzyEngineFactory::builder().build()).create_engine(target);
+    let mut matched: Vec<Selection> = labels
+        .into_iter()
+        .zip(selections)
+        .filter(|(_, selection)| !selection.is_dir)
+        .filter(|(label, _)| {
+            let item = PickerItem {
+                label: label.clone(),
+                index: 0,
+                preview: Preview::Text(String::new()),
+                marks: Arc::default(),
+            };
+            engine.match_item(Arc::new(item)).is_some()
+        })
+        .map(|(_, selection)| selection)
+        .collect();
+
+    Ok(match matched.len() {
+        0 => Resolution::NotFound,
+        1 if select_one || !interactive => Resolution::Found(vec![matched.remove(0)]),
+        _ if interactive => Resolution::Query,
+        _ => Resolution::Ambiguous(
+            matched
+                .into_iter()
+                .map(|selection| selection.path)
+                .collect(),
+        ),
+    })
+}
+
+// Run the picker until something is selected (Some) or it is cancelled
+// (None). `filters` and `query` keep their state for the next call, so the
+// picker can re-open where it was after an action.
+pub fn pick(
+    config: &Config,
+    root: &Path,
+    tasks_dir: &Path,
+    filters: &mut Filters,
+    query: &mut String,
+) -> io::Result<Option<(Action, Vec<Selection>)>> {
+    let rows = scan(tasks_dir)?;
+
+    loop {
+        let visible: Vec<&Row> = rows.iter().filter(|row| filters.matches(row)).collect();
+        let selections = entries(tasks_dir, &visible);
+        let (labels, header) = lines(tasks_dir, &visible, &selections);
+        let header = format!(
+            "{}\n{}\n{}",
+            KEY_HINTS,
+            filters.describe(rows.len() - visible.len()),
+            header
+        );
+
+        let mut keys = vec![TOGGLE_ALL, CYCLE_STATUS, CYCLE_PROJECT, CYCLE_TAG];
+        keys.extend(ACTION_KEYS.iter().map(|(key, _)| *key));
+        let expect = keys.join(",");
+        let options = SkimOptionsBuilder::default()
+            .height(Some(&config.picker.height))
+            .multi(true)
+            .preview(Some(""))
+            .preview_window(Some(&config.picker.preview_window))
+            .prompt(Some("Select a task: "))
+            .header(Some(&header))
+            .query(Some(query.as_str()))
+            .expect(Some(expect))
+            .build()
+            .unwrap();
+
+        let marks = Arc::new(Mutex::new(Vec::new()));
+        let (sender, receiver): (SkimItemSender, SkimItemReceiver) = unbounded();
+        for (index, (label, selection)) in labels.into_iter().zip(&selections).enumerate() {
+            let item = PickerItem {
+                label,
+                index,
+                preview: preview(config, root, tasks_dir, selection, &visible),
+                marks: Arc::clone(&marks),
+            };
+            let _ = sender.send(Arc::new(item));
+        }
+        drop(sender);
+
+        let Some(out) = Skim::run_with(&options, Some(receiver)) else {
+            return Ok(None);
+        };
+        if out.is_abort {
+            return Ok(None);
+        }
+        *query = out.query.clone();
+
+        let mut action = Action::Launch;
+        if let Event::EvActAccept(Some(key)) = &out.final_event {
+            match key.as_str() {
+                TOGGLE_ALL => {
+                    filters.all = !filters.all;
+                    filters.statuses.clear();
+                    continue;
+                }
+                CYCLE_STATUS => {
+                    let options: Vec<String> =
+                        status::STATUSES.iter().map(|s| s.to_string()).collect();
+                    filters.statuses = cycle(&filters.statuses, &options);
+                    continue;
+                }
+                CYCLE_PROJECT => {
+                    let options = distinct(rows.iter().map(|row| row.project.clone()));
+                    filters.projects = cycle(&filters.projects, &options);
+                    continue;
+                }
+                CYCLE_TAG => {
+                    let options = distinct(rows.iter().flat_map(|row| row.tags.clone()));
+                    filters.tags = cycle(&filters.tags, &options);
+                    continue;
+                }
+                key => {
+                    if let Some((_, chosen)) = ACTION_KEYS.iter().find(|(name, _)| *name == key) {
+                        action = *chosen;
+                    }
+                }
+            }
+        }
+
+        // Marked entries in the order they were marked
+        let indices: Vec<usize> = out
+            .selected_items
+            .iter()
+            .filter_map(|item| item.output().parse::<usize>().ok())
+            .collect();
+        let marks = marks.lock().map(|marks| marks.clone()).unwrap_or_default();
+        let mut selections: Vec<Option<Selection>> = selections.into_iter().map(Some).collect();
+        let picked: Vec<Selection> = mark_order(indices, &marks)
+            .into_iter()
+            .filter_map(|index| selections.get_mut(index).and_then(Option::take))
+            .collect();
+
+        // Nothing under the cursor (empty list): keep the picker open
+        if !picked.is_empty() {
+            return Ok(Some((action, picked)));
+        }
+    }
+}
+
+// skim returns marked entries in list order and has no hook for marking, but
+// each mark redraws the preview with the marked set. Entries keep the place
+// they first appeared in; a set of one with nothing marked is the cursor.
+fn track_marks(marks: &mut Vec<usize>, marked: &[usize]) {
+    marks.retain(|index| marked.contains(index));
+    let mut added: Vec<usize> = marked
+        .iter()
+        .filter(|index| !marks.contains(index))
+        .copied()
+        .collect();
+    added.sort_unstable();
+    marks.extend(added);
+}
+
+// `picked` in the order it was marked; entries marked while the preview was
+// hidden follow in list order
+fn mark_order(mut picked: Vec<usize>, marks: &[usize]) -> Vec<usize> {
+    picked.sort_unstable();
+    picked.dedup();
+    picked.sort_by_key(|index| {
+        marks
+            .iter()
+            .position(|mark| mark == index)
+            .unwrap_or(usize::MAX)
+    });
+    picked
+}
+
+// Step a single-value filter through `options`, then back to no filter
+fn cycle(current: &[String], options: &[String]) -> Vec<String> {
+    let next = match current {
+        [] => options.first(),
+        [value] => options
+            .iter()
+            .position(|option| option == value)
+            .and_then(|index| options.get(index + 1)),
+        _ => None,
+    };
+    next.cloned().into_iter().collect()
+}
+
+fn distinct(values: impl Iterator<Item = String>) -> Vec<String> {
+    let mut values: Vec<String> = values.filter(|value| !value.is_empty()).collect();
+    values.sort();
+    values.dedup();
+    values
+}
+
+fn scan(tasks_dir: &Path) -> io::Result<Vec<Row>> {
+    let mut files = Vec::new();
+    collect_files(tasks_dir, &mut files)?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "md"));
+    files.sort();
+
+    Ok(files
+        .into_iter()
+        .map(|path| {
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            match Task::parse(&path, &content) {
+                Ok(task) => {
+                    let status = status::current(&task).ok();
+                    Row {
+                        id: task.id(),
+                        status,
+                        status_text: status
+                            .map(str::to_string)
+                            .or(task.status.clone())
+                            .unwrap_or_default(),
+                        project: task.project.clone().unwrap_or_default(),
+                        title: task.title.clone().unwrap_or_default(),
+                        tags: task.tags(),
+                        date: task.updated.or(task.created),
+                        broken: false,
+                        path,
+                    }
+                }
+                Err(e) => Row {
+                    id: crate::task::file_id(&path),
+                    status: None,
+                    status_text: "?".to_string(),
+                    project: String::new(),
+                    title: format!("⚠ line {}: {}", e.line, e.message),
+                    tags: Vec::new(),
+                    date: None,
+                    broken: true,
+                    path,
+                },
+            }
+        })
+        .collect())
+}
+
+// Folders holding visible tasks, then the tasks themselves
+fn entries(tasks_dir: &Path, visible: &[&Row]) -> Vec<Selection> {
+    let mut folders: Vec<PathBuf> = Vec::new();
+    for row in visible {
+        let mut parent = row.path.parent();
+        while let Some(dir) = parent.filter(|dir| *dir != tasks_dir && dir.starts_with(tasks_dir)) {
+            if !folders.iter().any(|folder| folder == dir) {
+                folders.push(dir.to_path_buf());
+            }
+            parent = dir.parent();
+        }
+    }
+    folders.sort();
+
+    let mut selections: Vec<Selection> = folders
+        .into_iter()
+        .map(|folder| Selection {
+            tasks: visible
+                .iter()
+                .filter(|row| row.path.starts_with(&folder))
+                .map(|row| row.path.clone())
+                .collect(),
+            path: folder,
+            is_dir: true,
+        })
+        .collect();
+    selections.extend(visible.iter().map(|row| Selection {
+        path: row.path.clone(),
+        is_dir: false,
+        tasks: vec![row.path.clone()],
+    }));
+    selections
+}
+
+// Picker lines for `selections` and the column header, padded to line up
+fn lines(tasks_dir: &Path, visible: &[&Row], selections: &[Selection]) -> (Vec<String>, String) {
+    let titles: Vec<String> = visible.iter().map(|row| shorten(&row.title)).collect();
+    let width = |values: Vec<usize>, heading: &str| {
+        values
+            .into_iter()
+            .max()
+            .unwrap_or(0)
+            .max(heading.chars().count())
+    };
+    let id_width = width(
+        visible.iter().map(|row| row.id.chars().count()).collect(),
+        "ID",
+    );
+    let status_width = width(
+        visible
+            .iter()
+            .map(|row| row.status_text.chars().count())
+            .collect(),
+        "STATUS",
+    );
+    let project_width = width(
+        visible
+            .iter()
+            .map(|row| row.project.chars().count())
+            .collect(),
+        "PROJECT",
+    );
+    let title_width = width(
+        titles.iter().map(|title| title.chars().count()).collect(),
+        "TITLE",
+    );
+
+    let columns = |id: &str, status: &str, project: &str, title: &str, age: &str| {
+        format!(
+            "{}  {}  {}  {}  {}",
+            pad(id, id_width),
+            pad(status, status_width),
+            pad(project, project_width),
+            pad(title, title_width),
+            age
+        )
+        .trim_end()
+        .to_string()
+    };
+
+    let today = chrono::Local::now().date_naive();
+    let labels = selections
+        .iter()
+        .map(|selection| {
+            if selection.is_dir {
+                let folder = relative(tasks_dir, &selection.path);
+                return format!("📁 {}/  ({} tasks)", folder, selection.tasks.len());
+            }
+            let index = visible
+                .iter()
+                .position(|row| row.path == selection.path)
+                .unwrap_or(0);
+            let row = visible[index];
+            columns(
+                &row.id,
+                &row.status_text,
+                &row.project,
+                &titles[index],
+                &row.date.map(|date| age(date, today)).unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    let header = columns("ID", "STATUS", "PROJECT", "TITLE", "AGE");
+    (labels, header)
+}
+
+fn pad(value: &str, width: usize) -> String {
+    let count = value.chars().count();
+    format!("{}{}", value, " ".repeat(width.saturating_sub(count)))
+}
+
+fn shorten(title: &str) -> String {
+    if title.chars().count() <= MAX_TITLE_WIDTH {
+        return title.to_string();
+    }
+    let cut: String = title.chars().take(MAX_TITLE_WIDTH - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+// Time since `date`: "today", "3d", "2w", "5mo", "1y"
+fn age(date: NaiveDate, today: NaiveDate) -> String {
+    let days = (today - date).num_days().max(0);
+    match days {
+        0 => "today".to_string(),
+        1..=13 => format!("{}d", days),
+        14..=59 => format!("{}w", days / 7),
+        60..=364 => format!("{}mo", days / 30),
+        _ => format!("{}y", days / 365),
+    }
+}
+
+// The configured preview command for a task file; folders list their tasks
+fn preview(
+    config: &Config,
+    root: &Path,
+    tasks_dir: &Path,
+    selection: &Selection,
+    visible: &[&Row],
+) -> Preview {
+    if selection.is_dir {
+        let tasks: Vec<FolderTask> = visible
+            .iter()
+            .filter(|row| selection.tasks.contains(&row.path))
+            .map(|row| FolderTask {
+                id: row.id.clone(),
+                status: row.status_text.clone(),
+                title: row.title.clone(),
+            })
+            .collect();
+        let name = format!("{}/", relative(tasks_dir, &selection.path));
+        return Preview::Text(preview::folder(&name, &tasks));
+    }
+
+    let path = root.join(&selection.path);
+    if config.picker.preview_command.is_empty() {
+        return Preview::Task(path);
+    }
+    let quoted = shell_quote(&path.display().to_string());
+    Preview::Command(config.picker.preview_command.replace("{}", &quoted))
+}
+
+#[cfg(not(windows))]
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(windows)]
+fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_come_back_in_the_order_they_were_marked() {
+        let mut marks = Vec::new();
+        // The cursor starts on the first line, then 5, 2 and 7 are marked
+        for marked in [&[0][..], &[5], &[2, 5], &[2, 5, 7]] {
+            track_marks(&mut marks, marked);
+        }
+        assert_eq!(mark_order(vec![2, 5, 7], &marks), vec![5, 2, 7]);
+    }
+
+    #[test]
+    fn unmarking_and_marking_again_moves_an_entry_to_the_end() {
+        let mut marks = Vec::new();
+        for marked in [&[3][..], &[1, 3], &[1], &[1, 3]] {
+            track_marks(&mut marks, marked);
+        }
+        assert_eq!(mark_order(vec![1, 3], &marks), vec![1, 3]);
+
+        let mut marks = Vec::new();
+        for marked in [&[1][..], &[1, 3], &[3], &[1, 3]] {
+            track_marks(&mut marks, marked);
+        }
+        assert_eq!(mark_order(vec![1, 3], &marks), vec![3, 1]);
+    }
+
+    #[test]
+    fn untracked_marks_follow_in_list_order() {
+        assert_eq!(mark_order(vec![4, 1, 6, 1], &[6]), vec![6, 1, 4]);
+    }
+}
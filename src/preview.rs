@@ -0,0 +1,292 @@
+// Picker previews rendered in-process with ANSI styles: a header from the
+// frontmatter, the task body with highlighted headings and checkboxes, and
+// for folders a table of the tasks inside. Used unless
+// `picker.preview_command` is set.
+
+use crate::status;
+use crate::task::{self, Task};
+use std::fs;
+use std::path::Path;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+
+// Width of the goal progress bar and the rule under the header
+const BAR_WIDTH: usize = 10;
+const RULE_WIDTH: usize = 40;
+
+// A task as listed in a folder preview
+pub struct FolderTask {
+    pub id: String,
+    pub status: String,
+    pub title: String,
+}
+
+// Preview of the task file at `path`
+pub fn task(path: &Path) -> String {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => return format!("{}Could not read {}: {}{}", RED, path.display(), e, RESET),
+    };
+
+    match Task::parse(path, &content) {
+        Ok(task) => {
+            let mut output = header(&task);
+            output.push_str(&body(&content, &task.id()));
+            output
+        }
+        // Show the problem, then the file as it is
+        Err(e) => format!(
+            "{}⚠ line {}: {}{}\n\n{}",
+            RED, e.line, e.message, RESET, content
+        ),
+    }
+}
+
+// Preview of a folder: its tasks with their statuses, and how many are done
+pub fn folder(name: &str, tasks: &[FolderTask]) -> String {
+    let done = tasks.iter().filter(|task| task.status == "done").count();
+    let mut output = format!(
+        "{}📁 {}{}\n{}{} tasks, {} done{}\n{}\n",
+        BOLD,
+        name,
+        RESET,
+        DIM,
+        tasks.len(),
+        done,
+        RESET,
+        rule()
+    );
+
+    let width = tasks.iter().map(|task| task.id.len()).max().unwrap_or(0);
+    let status_width = tasks
+        .iter()
+        .map(|task| task.status.chars().count())
+        .max()
+        .unwrap_or(0);
+    for task in tasks {
+        output.push_str(&format!(
+            "{:width$}  {}{:status_width$}{}  {}\n",
+            task.id,
+            status_color(&task.status),
+            task.status,
+            RESET,
+            task.title,
+            width = width,
+            status_width = status_width
+        ));
+    }
+    output
+}
+
+// Id and title, then status, project, dates, tags and goal progress
+fn header(task: &Task) -> String {
+    let mut output = format!(
+        "{}{}  {}{}\n",
+        BOLD,
+        task.id(),
+        task.title.as_deref().unwrap_or("(untitled)"),
+        RESET
+    );
+
+    let status = match status::current(task) {
+        Ok(status) => format!("{}{}{}", status_color(status), status, RESET),
+        Err(_) => format!(
+            "{}{}?{}",
+            RED,
+            task.status.as_deref().unwrap_or_default(),
+            RESET
+        ),
+    };
+    let mut details = vec![status];
+    if let Some(project) = &task.project {
+        details.push(format!("project {}", project));
+    }
+    if let Some(created) = task.created {
+        details.push(format!("created {}", created));
+    }
+    if let Some(updated) = task.updated {
+        details.push(format!("updated {}", updated));
+    }
+    output.push_str(&details.join(&format!("{}  ·  {}", DIM, RESET)));
+    output.push('\n');
+
+    let tags = task.tags();
+    if !tags.is_empty() {
+        output.push_str(&format!("{}tags{} {}\n", DIM, RESET, tags.join(", ")));
+    }
+    if let Some(progress) = goal_progress(task) {
+        output.push_str(&progress);
+        output.push('\n');
+    }
+
+    output.push_str(&rule());
+    output.push('\n');
+    output
+}
+
+// "goals ████░░░░░░ 2/5" when the goals are checkboxes, otherwise their count
+fn goal_progress(task: &Task) -> Option<String> {
+    let section = task.section("Goals")?;
+    let goals: Vec<&str> = section
+        .content
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| line.starts_with("- ") || line.starts_with("* "))
+        .collect();
+    if goals.is_empty() {
+        return None;
+    }
+
+    let checked = goals
+        .iter()
+        .filter(|goal| goal[2..].starts_with("[x]") || goal[2..].starts_with("[X]"))
+        .count();
+    let boxes = checked
+        + goals
+            .iter()
+            .filter(|goal| goal[2..].starts_with("[ ]"))
+            .count();
+    if boxes == 0 {
+        return Some(format!("{}goals{} {}", DIM, RESET, goals.len()));
+    }
+
+    let filled = checked * BAR_WIDTH / boxes;
+    Some(format!(
+        "{}goals{} {}{}{}{}{} {}/{}",
+        DIM,
+        RESET,
+        GREEN,
+        "█".repeat(filled),
+        DIM,
+        "░".repeat(BAR_WIDTH - filled),
+        RESET,
+        checked,
+        boxes
+    ))
+}
+
+// The markdown after the frontmatter, without comments or the "# ID: Title"
+// heading the header already shows
+fn body(content: &str, id: &str) -> String {
+    let body = task::strip_comments(task::split_frontmatter(content).1);
+    let mut output = String::new();
+    let mut in_code = false;
+    let mut blank = true;
+
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            output.push_str(&format!("{}{}{}\n", DIM, line, RESET));
+            blank = false;
+            continue;
+        }
+        if in_code {
+            output.push_str(line);
+            output.push('\n');
+            blank = false;
+            continue;
+        }
+
+        // Collapse the blank runs left by removed comments
+        if line.trim().is_empty() {
+            if !blank {
+                output.push('\n');
+            }
+            blank = true;
+            continue;
+        }
+        blank = false;
+
+        let rendered = if let Some(heading) = line.strip_prefix("# ") {
+            if heading.starts_with(&format!("{}:", id)) {
+                blank = true;
+                continue;
+            }
+            format!("{}{}{}", BOLD, heading, RESET)
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            format!("{}{}{}{}", BOLD, CYAN, heading, RESET)
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            format!("{}{}{}", BOLD, heading, RESET)
+        } else {
+            list_item(line)
+        };
+        output.push_str(&rendered);
+        output.push('\n');
+    }
+
+    output.trim_end().to_string()
+}
+
+// Checkboxes and bullets as symbols, with inline **bold** and `code`
+fn list_item(line: &str) -> String {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let trimmed = line.trim_start();
+
+    let (marker, text) = if let Some(text) = trimmed
+        .strip_prefix("- [x] ")
+        .or_else(|| trimmed.strip_prefix("- [X] "))
+    {
+        (format!("{}✓{} ", GREEN, RESET), text)
+    } else if let Some(text) = trimmed.strip_prefix("- [ ] ") {
+        (format!("{}☐{} ", DIM, RESET), text)
+    } else if let Some(text) = trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))
+    {
+        ("• ".to_string(), text)
+    } else {
+        (String::new(), trimmed)
+    };
+
+    format!("{}{}{}", indent, marker, inline(text))
+}
+
+fn inline(text: &str) -> String {
+    let text = toggle(text, "**", BOLD);
+    toggle(&text, "`", YELLOW)
+}
+
+// Style the text between pairs of `marker`; an unpaired marker is kept
+fn toggle(text: &str, marker: &str, style: &str) -> String {
+    let parts: Vec<&str> = text.split(marker).collect();
+    if parts.len() < 3 {
+        return text.to_string();
+    }
+
+    let mut output = String::new();
+    for (index, part) in parts.iter().enumerate() {
+        if index % 2 == 0 {
+            output.push_str(part);
+        } else if index + 1 < parts.len() {
+            output.push_str(&format!("{}{}{}", style, part, RESET));
+        } else {
+            output.push_str(marker);
+            output.push_str(part);
+        }
+    }
+    output
+}
+
+fn status_color(status: &str) -> &'static str {
+    match status {
+        "draft" => DIM,
+        "ready" => CYAN,
+        "in-progress" => YELLOW,
+        "in-review" => MAGENTA,
+        "done" => GREEN,
+        "abandoned" => RED,
+        _ => RED,
+    }
+}
+
+fn rule() -> String {
+    format!("{}{}{}", DIM, "─".repeat(RULE_WIDTH), RESET)
+}
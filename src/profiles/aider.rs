@@ -0,0 +1,274 @@
+use super::{AgentProfile, Launch, Reference};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// Aider reads its project settings from this YAML file
+const CONFIG_FILE: &str = ".aider.conf.yml";
+
+// Where ensure_context_extracted() puts the rules; entries under this
+// prefix in the `read:` list belong to cdd
+const RULES_PREFIX: &str = ".context/_reference/rules/";
+
+pub struct AiderProfile;
+
+impl AgentProfile for AiderProfile {
+    fn id(&self) -> &str {
+        "aider"
+    }
+
+    fn name(&self) -> &str {
+        "Aider"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["aider-chat"]
+    }
+
+    fn config_dir(&self) -> &str {
+        CONFIG_FILE
+    }
+
+    // Aider has no custom commands; the rules are loaded as read-only files
+    fn command_folder(&self) -> Option<&str> {
+        None
+    }
+
+    fn is_installed(&self, root: &Path) -> bool {
+        let content = fs::read_to_string(root.join(CONFIG_FILE)).unwrap_or_default();
+        read_list(&content).is_some_and(|list| list.items.iter().any(|item| is_cdd_entry(item)))
+    }
+
+    fn launch(&self, launch: &Launch) -> process::Command {
+        // Files from the task's File System Diff are added as editable files
+        let mut cmd = process::Command::new("aider");
+        cmd.arg("--message").arg(launch.prompt);
+        cmd.args(launch.files);
+        cmd
+    }
+
+    fn shared_files(&self, root: &Path) -> Vec<PathBuf> {
+        vec![root.join(CONFIG_FILE)]
+    }
+
+    fn install(&self, root: &Path, reference: &Reference) -> std::io::Result<()> {
+        let config_path = root.join(CONFIG_FILE);
+        let content = fs::read_to_string(&config_path).unwrap_or_default();
+
+        let rule_paths: Vec<String> = reference
+            .rules
+            .iter()
+            .map(|rule| format!("{}{}", RULES_PREFIX, rule.name))
+            .collect();
+
+        let mut items = read_list(&content)
+            .map(|list| list.items)
+            .unwrap_or_default();
+        for path in rule_paths {
+            if !items.iter().any(|item| unquote(item) == path) {
+                items.push(path);
+            }
+        }
+
+        fs::write(&config_path, replace_read_list(&content, &items))
+    }
+
+    // Only take cdd's entries out of the read list, keep the rest of the config
+    fn uninstall(&self, root: &Path) -> std::io::Result<Vec<String>> {
+        let mut removed = Vec::new();
+        let config_path = root.join(CONFIG_FILE);
+        let Ok(content) = fs::read_to_string(&config_path) else {
+            return Ok(removed);
+        };
+        let Some(list) = read_list(&content) else {
+            return Ok(removed);
+        };
+        if !list.items.iter().any(|item| is_cdd_entry(item)) {
+            return Ok(removed);
+        }
+
+        let items: Vec<String> = list
+            .items
+            .into_iter()
+            .filter(|item| !is_cdd_entry(item))
+            .collect();
+        let updated = replace_read_list(&content, &items);
+
+        if updated.trim().is_empty() {
+            fs::remove_file(&config_path)?;
+        } else {
+            fs::write(&config_path, updated)?;
+        }
+        removed.push(format!("{} (cdd read entries)", CONFIG_FILE));
+
+        Ok(removed)
+    }
+}
+
+// The top-level `read:` key: its line range and raw items
+struct ReadList {
+    start: usize,
+    end: usize,
+    items: Vec<String>,
+}
+
+// Find `read:` written as a block list, a flow list (`[a, b]`) or a single value
+fn read_list(content: &str) -> Option<ReadList> {
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.iter().position(|line| line.starts_with("read:"))?;
+    let value = strip_comment(&lines[start]["read:".len()..]);
+
+    if !value.is_empty() {
+        let items = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Some(flow) => flow
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect(),
+            None => vec![value.to_string()],
+        };
+        return Some(ReadList {
+            start,
+            end: start + 1,
+            items,
+        });
+    }
+
+    // Block list: "- item" lines, indented or not, until the next key
+    let mut items = Vec::new();
+    let mut end = start + 1;
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        let trimmed = line.trim();
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            items.push(strip_comment(item).to_string());
+            end = index + 1;
+        } else if !(trimmed.is_empty() || trimmed.starts_with('#')) {
+            break;
+        }
+    }
+
+    Some(ReadList { start, end, items })
+}
+
+// Rewrite the `read:` key as a block list, dropping it when there are no items
+fn replace_read_list(content: &str, items: &[String]) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut block: Vec<String> = Vec::new();
+    if !items.is_empty() {
+        block.push("read:".to_string());
+        block.extend(items.iter().map(|item| format!("  - {}", item)));
+    }
+
+    let mut output: Vec<String> = match read_list(content) {
+        Some(list) => {
+            let mut output: Vec<String> =
+                lines[..list.start].iter().map(|l| l.to_string()).collect();
+            output.extend(block);
+            output.extend(lines[list.end..].iter().map(|l| l.to_string()));
+            output
+        }
+        None => {
+            let mut output: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+            output.extend(block);
+            output
+        }
+    };
+
+    while output.last().is_some_and(|line| line.trim().is_empty()) {
+        output.pop();
+    }
+    if output.is_empty() {
+        return String::new();
+    }
+    output.join("\n") + "\n"
+}
+
+fn is_cdd_entry(item: &str) -> bool {
+    unquote(item).starts_with(RULES_PREFIX)
+}
+
+fn unquote(item: &str) -> &str {
+    item.trim_matches(|c| c == '"' || c == '\'')
+}
+
+fn strip_comment(value: &str) -> &str {
+    match value.find(" #") {
+        Some(index) => value[..index].trim(),
+        None => value.trim(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::ReferenceFile;
+
+    // Install rules a.md and b.md over `existing` and return the new config
+    fn install(existing: Option<&str>) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        if let Some(existing) = existing {
+            fs::write(&path, existing).unwrap();
+        }
+        let reference = Reference {
+            commands: Vec::new(),
+            rules: ["a.md", "b.md"]
+                .iter()
+                .map(|name| ReferenceFile { name, contents: "" })
+                .collect(),
+        };
+        AiderProfile.install(dir.path(), &reference).unwrap();
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn install_adds_a_read_list_when_there_is_none() {
+        assert_eq!(
+            install(None),
+            "read:\n  - .context/_reference/rules/a.md\n  - .context/_reference/rules/b.md\n"
+        );
+        assert_eq!(
+            install(Some("model: sonnet\n")),
+            "model: sonnet\nread:\n  - .context/_reference/rules/a.md\n  - .context/_reference/rules/b.md\n"
+        );
+    }
+
+    #[test]
+    fn install_merges_into_an_inline_read_list() {
+        assert_eq!(
+            install(Some(
+                "read: [CONVENTIONS.md, \".context/_reference/rules/a.md\"] # mine\nmodel: sonnet\n"
+            )),
+            "read:\n  - CONVENTIONS.md\n  - \".context/_reference/rules/a.md\"\n  - .context/_reference/rules/b.md\nmodel: sonnet\n"
+        );
+    }
+
+    #[test]
+    fn install_merges_into_a_block_read_list() {
+        let existing = "model: sonnet\nread:\n- CONVENTIONS.md  # team rules\n\n  - docs/api.md\nauto-commits: false\n";
+        assert_eq!(
+            install(Some(existing)),
+            "model: sonnet\nread:\n  - CONVENTIONS.md\n  - docs/api.md\n  - .context/_reference/rules/a.md\n  - .context/_reference/rules/b.md\nauto-commits: false\n"
+        );
+    }
+
+    #[test]
+    fn uninstall_keeps_the_entries_it_did_not_add() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(
+            &path,
+            "read:\n  - CONVENTIONS.md\n  - .context/_reference/rules/a.md\nmodel: sonnet\n",
+        )
+        .unwrap();
+        assert!(AiderProfile.is_installed(dir.path()));
+
+        AiderProfile.uninstall(dir.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "read:\n  - CONVENTIONS.md\nmodel: sonnet\n"
+        );
+        assert!(!AiderProfile.is_installed(dir.path()));
+    }
+}
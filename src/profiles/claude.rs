@@ -0,0 +1,33 @@
+use super::{AgentProfile, Launch};
+use std::process;
+
+pub struct ClaudeProfile;
+
+impl AgentProfile for ClaudeProfile {
+    fn id(&self) -> &str {
+        "claude"
+    }
+
+    fn name(&self) -> &str {
+        "Claude Code"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["claudecode", "claude-code"]
+    }
+
+    fn config_dir(&self) -> &str {
+        ".claude"
+    }
+
+    fn command_folder(&self) -> Option<&str> {
+        Some("commands") // plural
+    }
+
+    fn launch(&self, launch: &Launch) -> process::Command {
+        // claude just takes the prompt as an argument
+        let mut cmd = process::Command::new("claude");
+        cmd.arg(launch.prompt);
+        cmd
+    }
+}
@@ -0,0 +1,76 @@
+use super::managed_block::{has_managed_block, remove_managed_block, write_managed_block};
+use super::{render_rules_section, AgentProfile, Launch, Reference};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// Codex reads project instructions from AGENTS.md at the repository root
+const AGENTS_FILE: &str = "AGENTS.md";
+
+pub struct CodexProfile;
+
+impl AgentProfile for CodexProfile {
+    fn id(&self) -> &str {
+        "codex"
+    }
+
+    fn name(&self) -> &str {
+        "Codex CLI"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["codex-cli", "openai"]
+    }
+
+    fn config_dir(&self) -> &str {
+        ".codex"
+    }
+
+    fn command_folder(&self) -> Option<&str> {
+        Some("prompts")
+    }
+
+    // Codex only loads custom prompts from $CODEX_HOME/prompts, not per project
+    fn commands_path(&self, _root: &Path) -> Option<PathBuf> {
+        Some(codex_home().join(self.command_folder()?))
+    }
+
+    fn is_installed(&self, root: &Path) -> bool {
+        has_managed_block(&root.join(AGENTS_FILE))
+    }
+
+    fn launch(&self, launch: &Launch) -> process::Command {
+        // codex takes the initial prompt as an argument
+        let mut cmd = process::Command::new("codex");
+        cmd.arg(launch.prompt);
+        cmd
+    }
+
+    fn shared_files(&self, root: &Path) -> Vec<PathBuf> {
+        vec![root.join(AGENTS_FILE)]
+    }
+
+    fn install(&self, root: &Path, reference: &Reference) -> std::io::Result<()> {
+        write_managed_block(
+            &root.join(AGENTS_FILE),
+            &render_rules_section(&reference.rules),
+        )
+    }
+
+    // Prompts in $CODEX_HOME are shared with other projects, so only the
+    // project's AGENTS.md block is removed
+    fn uninstall(&self, root: &Path) -> std::io::Result<Vec<String>> {
+        let mut removed = Vec::new();
+        if remove_managed_block(&root.join(AGENTS_FILE))? {
+            removed.push(format!("{} (cdd section)", AGENTS_FILE));
+        }
+        Ok(removed)
+    }
+}
+
+fn codex_home() -> PathBuf {
+    match env::var_os("CODEX_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => dirs::home_dir().unwrap_or_default().join(".codex"),
+    }
+}
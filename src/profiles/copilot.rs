@@ -0,0 +1,84 @@
+use super::managed_block::{has_managed_block, remove_managed_block, write_managed_block};
+use super::{render_rules_section, AgentProfile, CommandFormat, Launch, Reference};
+use std::path::{Path, PathBuf};
+use std::process;
+
+// Folder inside .github that holds reusable prompt files
+const PROMPTS_FOLDER: &str = "prompts";
+
+// Repository-wide custom instructions read by Copilot
+const INSTRUCTIONS_FILE: &str = "copilot-instructions.md";
+
+pub struct CopilotProfile;
+
+impl CopilotProfile {
+    fn instructions_path(&self, root: &Path) -> PathBuf {
+        root.join(self.config_dir()).join(INSTRUCTIONS_FILE)
+    }
+}
+
+impl AgentProfile for CopilotProfile {
+    fn id(&self) -> &str {
+        "copilot"
+    }
+
+    fn name(&self) -> &str {
+        "GitHub Copilot"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["github-copilot", "vscode"]
+    }
+
+    fn config_dir(&self) -> &str {
+        ".github"
+    }
+
+    fn command_folder(&self) -> Option<&str> {
+        Some(PROMPTS_FOLDER)
+    }
+
+    fn command_format(&self) -> CommandFormat {
+        CommandFormat::PromptFile
+    }
+
+    // .github/prompts may exist for other reasons, so look for our section
+    fn is_installed(&self, root: &Path) -> bool {
+        has_managed_block(&self.instructions_path(root))
+    }
+
+    fn launch(&self, launch: &Launch) -> process::Command {
+        // Opens a Copilot chat in agent mode in the current VS Code window
+        let mut cmd = process::Command::new("code");
+        cmd.arg("chat")
+            .arg("--mode")
+            .arg("agent")
+            .arg(launch.prompt);
+        cmd
+    }
+
+    fn shared_files(&self, root: &Path) -> Vec<PathBuf> {
+        vec![self.instructions_path(root)]
+    }
+
+    fn install(&self, root: &Path, reference: &Reference) -> std::io::Result<()> {
+        write_managed_block(
+            &self.instructions_path(root),
+            &render_rules_section(&reference.rules),
+        )
+    }
+
+    fn uninstall(&self, root: &Path) -> std::io::Result<Vec<String>> {
+        let mut removed = Vec::new();
+
+        if remove_managed_block(&self.instructions_path(root))? {
+            removed.push(format!(
+                "{}/{} (cdd section)",
+                self.config_dir(),
+                INSTRUCTIONS_FILE
+            ));
+        }
+
+        Ok(removed)
+    }
+}
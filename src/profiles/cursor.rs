@@ -0,0 +1,77 @@
+use super::{
+    command_files, markdown_title, yaml_string, AgentProfile, Launch, PlannedFile, Reference,
+    ReferenceFile,
+};
+use crate::rules;
+use std::path::Path;
+use std::process;
+
+// Folder inside .cursor that holds project rules
+const RULES_FOLDER: &str = "rules";
+
+// Prefix for generated rule files so they never clash with the user's own rules
+const RULE_PREFIX: &str = "cdd-";
+
+pub struct CursorProfile;
+
+impl AgentProfile for CursorProfile {
+    fn id(&self) -> &str {
+        "cursor"
+    }
+
+    fn name(&self) -> &str {
+        "Cursor"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["cursor-agent"]
+    }
+
+    fn config_dir(&self) -> &str {
+        ".cursor"
+    }
+
+    fn command_folder(&self) -> Option<&str> {
+        Some("commands")
+    }
+
+    fn launch(&self, launch: &Launch) -> process::Command {
+        // The Cursor CLI takes the initial prompt as an argument
+        let mut cmd = process::Command::new("cursor-agent");
+        cmd.arg(launch.prompt);
+        cmd
+    }
+
+    fn planned_files(&self, root: &Path, reference: &Reference) -> Vec<PlannedFile> {
+        let mut files = command_files(self, root, &reference.commands);
+
+        // Convert rules into .mdc files with Cursor's rule frontmatter
+        let rules_dir = root.join(self.config_dir()).join(RULES_FOLDER);
+        files.extend(reference.rules.iter().map(|rule| PlannedFile {
+            path: rules_dir.join(rule_file_name(rule.name)),
+            contents: render_rule(rule),
+        }));
+
+        files
+    }
+}
+
+// rs.md -> cdd-rs.mdc
+fn rule_file_name(name: &str) -> String {
+    let stem = name.strip_suffix(".md").unwrap_or(name);
+    format!("{}{}.mdc", RULE_PREFIX, stem)
+}
+
+fn render_rule(rule: &ReferenceFile) -> String {
+    // Rules without globs in their frontmatter are always applied
+    let parsed = rules::parse(rule.name, rule.contents);
+    let description = markdown_title(&parsed.body).unwrap_or(rule.name);
+
+    format!(
+        "---\ndescription: {}\nglobs: {}\nalwaysApply: {}\n---\n\n{}",
+        yaml_string(description),
+        parsed.globs.join(","),
+        parsed.globs.is_empty(),
+        parsed.body
+    )
+}
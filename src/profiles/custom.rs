@@ -0,0 +1,210 @@
+use super::{AgentProfile, Launch};
+use crate::files::relative;
+use crate::manifest::sha256_hex;
+use crate::new_task::cancelled;
+use inquire::Confirm;
+use serde::Deserialize;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+
+// A profile declared in a TOML file, so new agents can be added without
+// changing cdd itself. Example `.context/profiles/myagent.toml`:
+//
+//     id = "myagent"
+//     name = "My Agent"
+//     aliases = ["ma"]
+//     config_dir = ".myagent"
+//     command_folder = "commands"
+//     command = "myagent"
+//     args = ["--prompt", "{prompt}"]
+//
+// `config_dir` and `command_folder` must be relative paths inside the
+// project. Profiles in a project's `.context/profiles/` can only add agents,
+// not replace the built-in ones, and cdd neither launches them nor writes or
+// removes their files until the user trusts them.
+#[derive(Debug, Deserialize)]
+pub struct CustomProfile {
+    id: String,
+    name: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    config_dir: String,
+    command_folder: String,
+    command: String,
+    // Launch arguments; "{prompt}" is replaced with the task prompt
+    #[serde(default = "default_args")]
+    args: Vec<String>,
+    // Set for profiles loaded from the project rather than the user
+    #[serde(skip)]
+    project_file: Option<PathBuf>,
+}
+
+fn default_args() -> Vec<String> {
+    vec!["{prompt}".to_string()]
+}
+
+impl CustomProfile {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let profile: Self = toml::from_str(content).map_err(|e| e.to_string())?;
+        for (key, value) in [
+            ("config_dir", &profile.config_dir),
+            ("command_folder", &profile.command_folder),
+        ] {
+            if !stays_inside(value) {
+                return Err(format!(
+                    "{} = \"{}\" must be a relative path without '..'",
+                    key, value
+                ));
+            }
+        }
+        Ok(profile)
+    }
+
+    pub fn from_project_file(path: &Path) -> Result<Self, String> {
+        let mut profile = Self::from_file(path)?;
+        profile.project_file = Some(path.to_path_buf());
+        Ok(profile)
+    }
+}
+
+// Only plain folder names: no root, drive prefix or ".."
+fn stays_inside(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+fn trust_store() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config/cdd/trusted_profiles"))
+}
+
+fn is_trusted_hash(trusted: &str, hash: &str) -> bool {
+    trusted
+        .lines()
+        .any(|line| line.split_whitespace().next() == Some(hash))
+}
+
+// Built-in and user profiles, and project profiles whose current file the
+// user has already trusted. Never asks.
+pub fn is_trusted(profile: &dyn AgentProfile) -> bool {
+    let Some(path) = profile.project_file() else {
+        return true;
+    };
+    let (Ok(contents), Some(store)) = (fs::read(path), trust_store()) else {
+        return false;
+    };
+    let trusted = fs::read_to_string(store).unwrap_or_default();
+    is_trusted_hash(&trusted, &sha256_hex(&contents))
+}
+
+// Ask before launching the command of a project profile for the first time.
+// The file's hash is remembered in ~/.config/cdd/trusted_profiles, so an
+// edited file is asked about again. Without a terminal nothing is trusted.
+pub fn trust(root: &Path, path: &Path) -> io::Result<bool> {
+    let contents = fs::read(path)?;
+    let hash = sha256_hex(&contents);
+    let Some(store) = trust_store() else {
+        return Ok(false);
+    };
+    let trusted = fs::read_to_string(&store).unwrap_or_default();
+    if is_trusted_hash(&trusted, &hash) {
+        return Ok(true);
+    }
+
+    let profile = CustomProfile::from_file(path)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let command = std::iter::once(&profile.command)
+        .chain(&profile.args)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!(
+        "⚠ {} comes from this repository and runs:\n\n    {}\n",
+        relative(root, path),
+        command
+    );
+    if !io::stdin().is_terminal() {
+        return Ok(false);
+    }
+
+    let answer = Confirm::new("Trust this profile and launch it?")
+        .with_default(false)
+        .prompt()
+        .map_err(cancelled)?;
+    if answer {
+        if let Some(parent) = store.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let line = format!("{}  {}\n", hash, path.display());
+        fs::write(&store, trusted + &line)?;
+    }
+    Ok(answer)
+}
+
+impl AgentProfile for CustomProfile {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        self.aliases.iter().map(String::as_str).collect()
+    }
+
+    fn config_dir(&self) -> &str {
+        &self.config_dir
+    }
+
+    fn command_folder(&self) -> Option<&str> {
+        Some(&self.command_folder)
+    }
+
+    fn launch(&self, launch: &Launch) -> process::Command {
+        let mut cmd = process::Command::new(&self.command);
+        for arg in &self.args {
+            cmd.arg(arg.replace("{prompt}", launch.prompt));
+        }
+        cmd
+    }
+
+    fn project_file(&self) -> Option<&Path> {
+        self.project_file.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(config_dir: &str, command_folder: &str) -> Result<CustomProfile, String> {
+        CustomProfile::parse(&format!(
+            "id = \"ma\"\nname = \"My Agent\"\nconfig_dir = \"{}\"\n\
+             command_folder = \"{}\"\ncommand = \"ma\"\n",
+            config_dir, command_folder
+        ))
+    }
+
+    #[test]
+    fn folders_inside_the_project_are_accepted() {
+        assert!(profile(".myagent", "commands").is_ok());
+        assert!(profile("./tools/agent", "prompts/cdd").is_ok());
+    }
+
+    #[test]
+    fn folders_outside_the_project_are_rejected() {
+        assert!(profile("../..", "commands").is_err());
+        assert!(profile(".myagent", "../../.ssh").is_err());
+        assert!(profile("tools/../../x", "commands").is_err());
+        assert!(profile("/etc", "commands").is_err());
+    }
+}
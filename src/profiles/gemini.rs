@@ -0,0 +1,37 @@
+use super::{AgentProfile, CommandFormat, Launch};
+use std::process;
+
+pub struct GeminiProfile;
+
+impl AgentProfile for GeminiProfile {
+    fn id(&self) -> &str {
+        "gemini"
+    }
+
+    fn name(&self) -> &str {
+        "Gemini CLI"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["gemini-cli"]
+    }
+
+    fn config_dir(&self) -> &str {
+        ".gemini"
+    }
+
+    fn command_folder(&self) -> Option<&str> {
+        Some("commands")
+    }
+
+    fn command_format(&self) -> CommandFormat {
+        CommandFormat::Toml
+    }
+
+    fn launch(&self, launch: &Launch) -> process::Command {
+        // --prompt-interactive runs the prompt and keeps the session open
+        let mut cmd = process::Command::new("gemini");
+        cmd.arg("--prompt-interactive").arg(launch.prompt);
+        cmd
+    }
+}
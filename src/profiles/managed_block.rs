@@ -0,0 +1,62 @@
+use std::fs;
+use std::path::Path;
+
+// Markers around the section of a shared file that cdd owns. Everything
+// outside the markers belongs to the user and is left untouched.
+const BLOCK_BEGIN: &str = "<!-- cdd:begin (managed by cdd, do not edit) -->";
+const BLOCK_END: &str = "<!-- cdd:end -->";
+
+// Insert or replace the managed block in `path`, creating the file if needed
+pub fn write_managed_block(path: &Path, content: &str) -> std::io::Result<()> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let block = format!("{}\n{}\n{}\n", BLOCK_BEGIN, content.trim_end(), BLOCK_END);
+
+    let updated = match find_block(&existing) {
+        Some((start, end)) => format!("{}{}{}", &existing[..start], block, &existing[end..]),
+        None if existing.trim().is_empty() => block,
+        None => format!("{}\n\n{}", existing.trim_end(), block),
+    };
+
+    fs::write(path, updated)
+}
+
+// Remove the managed block from `path`, deleting the file if nothing else is
+// left. Returns whether a block was removed.
+pub fn remove_managed_block(path: &Path) -> std::io::Result<bool> {
+    let Ok(existing) = fs::read_to_string(path) else {
+        return Ok(false);
+    };
+    let Some((start, end)) = find_block(&existing) else {
+        return Ok(false);
+    };
+
+    let before = existing[..start].trim_end();
+    let after = existing[end..].trim_start();
+    let remaining = match (before.is_empty(), after.is_empty()) {
+        (false, false) => format!("{}\n\n{}", before, after),
+        _ => format!("{}{}", before, after),
+    };
+
+    if remaining.trim().is_empty() {
+        fs::remove_file(path)?;
+    } else {
+        fs::write(path, format!("{}\n", remaining.trim_end()))?;
+    }
+
+    Ok(true)
+}
+
+pub fn has_managed_block(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| find_block(&content).is_some())
+}
+
+// Byte range of the block including the end marker and its trailing newline
+fn find_block(content: &str) -> Option<(usize, usize)> {
+    let start = content.find(BLOCK_BEGIN)?;
+    let end_marker = start + content[start..].find(BLOCK_END)?;
+    let mut end = end_marker + BLOCK_END.len();
+    if content[end..].starts_with('\n') {
+        end += 1;
+    }
+    Some((start, end))
+}
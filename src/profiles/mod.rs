@@ -0,0 +1,229 @@
+mod aider;
+mod claude;
+mod codex;
+mod copilot;
+mod cursor;
+mod custom;
+mod gemini;
+mod managed_block;
+mod opencode;
+mod registry;
+
+pub use aider::AiderProfile;
+pub use claude::ClaudeProfile;
+pub use codex::CodexProfile;
+pub use copilot::CopilotProfile;
+pub use cursor::CursorProfile;
+pub use custom::{is_trusted, trust, CustomProfile};
+pub use gemini::GeminiProfile;
+pub use opencode::OpenCodeProfile;
+pub use registry::ProfileRegistry;
+
+use crate::rules;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// A file shipped in the embedded _reference directory (e.g. a command or a rule)
+pub struct ReferenceFile<'a> {
+    pub name: &'a str,
+    pub contents: &'a str,
+}
+
+// What an agent is started with
+pub struct Launch<'a> {
+    pub prompt: &'a str,
+    // Files the selected task expects to touch (from its File System Diff)
+    pub files: &'a [PathBuf],
+}
+
+// The embedded files a profile installs from
+pub struct Reference<'a> {
+    pub commands: Vec<ReferenceFile<'a>>,
+    pub rules: Vec<ReferenceFile<'a>>,
+}
+
+// How command files are written into an agent's command folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandFormat {
+    // Plain markdown, copied as-is (Claude Code, OpenCode)
+    Markdown,
+    // TOML with `description` and `prompt` fields (Gemini CLI)
+    Toml,
+    // `.prompt.md` files with `mode`/`description` frontmatter (GitHub Copilot)
+    PromptFile,
+}
+
+#[derive(Serialize)]
+struct TomlCommand<'a> {
+    description: &'a str,
+    prompt: &'a str,
+}
+
+impl CommandFormat {
+    // Convert an embedded command into (file name, contents) for this format
+    pub fn render(&self, command: &ReferenceFile) -> (String, String) {
+        match self {
+            CommandFormat::Markdown => (command.name.to_string(), command.contents.to_string()),
+            CommandFormat::Toml => {
+                let stem = command.name.strip_suffix(".md").unwrap_or(command.name);
+                let toml_command = TomlCommand {
+                    description: markdown_title(command.contents).unwrap_or(stem),
+                    prompt: command.contents,
+                };
+                // The serializer picks multi-line strings and escapes quotes for us
+                let contents = toml::to_string_pretty(&toml_command)
+                    .expect("string fields always serialize to TOML");
+                (format!("{}.toml", stem), contents)
+            }
+            CommandFormat::PromptFile => {
+                let stem = command.name.strip_suffix(".md").unwrap_or(command.name);
+                let description = markdown_title(command.contents).unwrap_or(stem);
+                let contents = format!(
+                    "---\nmode: agent\ndescription: {}\n---\n\n{}",
+                    yaml_string(description),
+                    command.contents
+                );
+                (format!("{}.prompt.md", stem), contents)
+            }
+        }
+    }
+}
+
+// Everything cdd needs to know about a coding agent.
+//
+// Adding a new agent means implementing this trait and registering it in
+// `ProfileRegistry::builtin()`, or dropping a TOML file into a profiles
+// directory (see `CustomProfile`).
+pub trait AgentProfile {
+    // Stable identifier used on the command line (e.g. "claude")
+    fn id(&self) -> &str;
+
+    // Human-readable name shown in menus and messages (e.g. "Claude Code")
+    fn name(&self) -> &str;
+
+    // Extra names accepted by `--profile` besides the id
+    fn aliases(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    // Agent configuration directory, relative to the project root (e.g.
+    // ".claude"); a file for agents configured by a single file (Aider)
+    fn config_dir(&self) -> &str;
+
+    // Folder inside `config_dir` that holds command files (e.g. "commands"),
+    // or None for agents without custom commands
+    fn command_folder(&self) -> Option<&str>;
+
+    fn command_format(&self) -> CommandFormat {
+        CommandFormat::Markdown
+    }
+
+    // Build the process invocation that starts the agent on a task
+    fn launch(&self, launch: &Launch) -> process::Command;
+
+    // The project file that declared this profile. Its command comes from
+    // the repository, so it only runs once the user trusts it.
+    fn project_file(&self) -> Option<&Path> {
+        None
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.id().eq_ignore_ascii_case(name)
+            || self.name().eq_ignore_ascii_case(name)
+            || self
+                .aliases()
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+
+    fn commands_path(&self, root: &Path) -> Option<PathBuf> {
+        let folder = self.command_folder()?;
+        Some(root.join(self.config_dir()).join(folder))
+    }
+
+    fn is_installed(&self, root: &Path) -> bool {
+        self.commands_path(root).is_some_and(|path| path.exists())
+    }
+
+    // Files cdd owns and writes for this profile (commands, rule files).
+    // They are tracked in the install manifest so uninstall and upgrades
+    // can tell whether the user changed them.
+    fn planned_files(&self, root: &Path, reference: &Reference) -> Vec<PlannedFile> {
+        command_files(self, root, &reference.commands)
+    }
+
+    // Files shared with the user that `install` edits in place (e.g. AGENTS.md)
+    fn shared_files(&self, _root: &Path) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    // Setup beyond writing planned files, such as managed sections in shared files
+    fn install(&self, _root: &Path, _reference: &Reference) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    // Undo `install` (planned files are removed via the manifest), returning
+    // a label for each changed item
+    fn uninstall(&self, _root: &Path) -> std::io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+// A file a profile writes, with its final contents
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+// Command files rendered into the profile's command folder
+pub fn command_files<P: AgentProfile + ?Sized>(
+    profile: &P,
+    root: &Path,
+    commands: &[ReferenceFile],
+) -> Vec<PlannedFile> {
+    let Some(target_commands_dir) = profile.commands_path(root) else {
+        return Vec::new();
+    };
+
+    commands
+        .iter()
+        .map(|command| {
+            let (file_name, contents) = profile.command_format().render(command);
+            PlannedFile {
+                path: target_commands_dir.join(file_name),
+                contents,
+            }
+        })
+        .collect()
+}
+
+// First markdown heading of a file, used as a short description
+pub fn markdown_title(contents: &str) -> Option<&str> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(str::trim)
+}
+
+// Quote a value for YAML frontmatter (JSON strings are valid YAML scalars)
+pub fn yaml_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+// All rules concatenated under a short header, for agents that read a single
+// instructions file instead of separate rule files
+pub fn render_rules_section(files: &[ReferenceFile]) -> String {
+    let mut content = String::from(
+        "# Context-Driven Development\n\n\
+         Tasks live in `.context/tasks/`. Follow these project rules when working on them.\n",
+    );
+
+    for rule in files {
+        content.push('\n');
+        content.push_str(rules::parse(rule.name, rule.contents).body.trim_end());
+        content.push('\n');
+    }
+
+    content
+}
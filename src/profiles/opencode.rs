@@ -0,0 +1,62 @@
+use super::{AgentProfile, Launch};
+use std::fs;
+use std::process;
+
+pub struct OpenCodeProfile;
+
+impl AgentProfile for OpenCodeProfile {
+    fn id(&self) -> &str {
+        "opencode"
+    }
+
+    fn name(&self) -> &str {
+        "OpenCode"
+    }
+
+    fn aliases(&self) -> Vec<&str> {
+        vec!["open-code"]
+    }
+
+    fn config_dir(&self) -> &str {
+        ".opencode"
+    }
+
+    fn command_folder(&self) -> Option<&str> {
+        Some("command") // singular
+    }
+
+    fn launch(&self, launch: &Launch) -> process::Command {
+        let mut cmd = process::Command::new("opencode");
+
+        // Try to use user's last used model from OpenCode
+        if let Some(model) = get_opencode_last_model() {
+            cmd.arg("--model").arg(&model);
+        }
+
+        cmd.arg("--prompt").arg(launch.prompt);
+        cmd
+    }
+}
+
+fn get_opencode_last_model() -> Option<String> {
+    // OpenCode stores recent models in ~/.local/state/opencode/model.json
+    let state_file = dirs::home_dir()?.join(".local/state/opencode/model.json");
+
+    if !state_file.exists() {
+        return None;
+    }
+
+    // Read and parse the JSON file
+    let content = fs::read_to_string(&state_file).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+    // Get the first item from the "recent" array
+    let recent = json.get("recent")?.as_array()?;
+    let last_model = recent.first()?;
+
+    // Extract providerID and modelID
+    let provider_id = last_model.get("providerID")?.as_str()?;
+    let model_id = last_model.get("modelID")?.as_str()?;
+
+    Some(format!("{}/{}", provider_id, model_id))
+}
@@ -0,0 +1,126 @@
+use super::{
+    is_trusted, AgentProfile, AiderProfile, ClaudeProfile, CodexProfile, CopilotProfile,
+    CursorProfile, CustomProfile, GeminiProfile, OpenCodeProfile,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The set of agent profiles cdd knows about, in menu order
+pub struct ProfileRegistry {
+    profiles: Vec<Box<dyn AgentProfile>>,
+}
+
+impl ProfileRegistry {
+    pub fn builtin() -> Self {
+        ProfileRegistry {
+            profiles: vec![
+                Box::new(ClaudeProfile),
+                Box::new(OpenCodeProfile),
+                Box::new(CursorProfile),
+                Box::new(GeminiProfile),
+                Box::new(CodexProfile),
+                Box::new(CopilotProfile),
+                Box::new(AiderProfile),
+            ],
+        }
+    }
+
+    // Built-in profiles plus any custom profiles found in
+    // ~/.config/cdd/profiles/*.toml and <project>/.context/profiles/*.toml.
+    // User profiles may replace built-in ones; project profiles, which come
+    // with whatever repository was cloned, may only add new agents.
+    pub fn load(project_root: &Path) -> Self {
+        let mut registry = Self::builtin();
+
+        if let Some(home) = dirs::home_dir() {
+            for path in profile_files(&home.join(".config/cdd/profiles")) {
+                match CustomProfile::from_file(&path) {
+                    Ok(profile) => registry.register(Box::new(profile)),
+                    Err(e) => {
+                        eprintln!("Warning: Skipping profile {}: {}", path.display(), e);
+                    }
+                }
+            }
+        }
+
+        for path in profile_files(&project_root.join(".context/profiles")) {
+            let profile = match CustomProfile::from_project_file(&path) {
+                Ok(profile) => profile,
+                Err(e) => {
+                    eprintln!("Warning: Skipping profile {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            let names = std::iter::once(profile.id())
+                .chain(std::iter::once(profile.name()))
+                .chain(profile.aliases());
+            if let Some(existing) = names
+                .filter_map(|name| registry.find(name))
+                .map(|existing| existing.id().to_string())
+                .next()
+            {
+                eprintln!(
+                    "Warning: Skipping profile {}: project profiles can't replace the '{}' profile; \
+                     put it in ~/.config/cdd/profiles to override it",
+                    path.display(),
+                    existing
+                );
+                continue;
+            }
+            registry.register(Box::new(profile));
+        }
+
+        registry
+    }
+
+    // Add a profile; a profile with the same id replaces the existing one
+    pub fn register(&mut self, profile: Box<dyn AgentProfile>) {
+        if let Some(existing) = self.profiles.iter_mut().find(|p| p.id() == profile.id()) {
+            *existing = profile;
+        } else {
+            self.profiles.push(profile);
+        }
+    }
+
+    pub fn find(&self, name: &str) -> Option<&dyn AgentProfile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.matches(name))
+            .map(|profile| profile.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn AgentProfile> {
+        self.profiles.iter().map(|profile| profile.as_ref())
+    }
+
+    // Profiles cdd may install, upgrade and remove files for without asking
+    pub fn trusted(&self) -> impl Iterator<Item = &dyn AgentProfile> {
+        self.iter().filter(|profile| is_trusted(*profile))
+    }
+
+    pub fn ids(&self) -> Vec<&str> {
+        self.iter().map(|profile| profile.id()).collect()
+    }
+
+    // Profiles recorded by `cdd install` or whose files are present in `root`
+    pub fn installed(&self, root: &Path, recorded: &[String]) -> Vec<&dyn AgentProfile> {
+        self.iter()
+            .filter(|profile| {
+                recorded.iter().any(|id| id == profile.id()) || profile.is_installed(root)
+            })
+            .collect()
+    }
+}
+
+// The *.toml files in `dir`, sorted
+fn profile_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    paths
+}
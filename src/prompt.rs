@@ -0,0 +1,353 @@
+// Agent prompts rendered from templates. The first of these found in
+// `.context/prompts/`, then `~/.config/cdd/prompts/`, is used:
+//
+//     <agent>.<type>.md   e.g. claude.bug.md
+//     <type>.md           e.g. bug.md, for the task's `type` frontmatter key
+//     <agent>.md          e.g. claude.md
+//     default.md
+//
+// Without any, the prompt is `run.prompt_prefix` followed by the task file
+// and the rules scoped to the files it touches (see rules.rs).
+// Templates use a small mustache-like syntax:
+//
+//     {{task.title}}                      a variable, see VARIABLES
+//     {{#if lessons}}...{{else}}...{{/if}} kept when the variable isn't empty
+//     {{> footer}}                        footer.md from the same directories
+
+use crate::config::{self, Config};
+use crate::file_system_diff;
+use crate::files::relative;
+use crate::rules::{self, Rule};
+use crate::status;
+use crate::task::{self, Task};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const VARIABLES: &[&str] = &[
+    "prompt_prefix",
+    "agent",
+    "task.id",
+    "task.title",
+    "task.project",
+    "task.status",
+    "task.type",
+    "task.path",
+    "task.body",
+    "task.content",
+    "rules",
+    "lessons",
+    "git.branch",
+];
+
+const BUILT_IN: &str = "{{prompt_prefix}}
+
+{{task.content}}
+{{#if rules}}
+
+---
+
+Follow these project rules for the files this task touches:
+
+{{rules}}
+{{/if}}
+";
+
+// Agents take the prompt as one command-line argument, which Linux caps at
+// 128 KiB counting the terminating NUL
+pub const MAX_BYTES: usize = 128 * 1024 - 1;
+
+// Includes nested deeper than this are taken to be a cycle
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+// What a prompt is about: one task, or several bundled into `content`
+pub struct Subject<'a> {
+    pub task: Option<&'a Task>,
+    pub content: &'a str,
+}
+
+// The prompt for launching `agent` on `subject`
+pub fn render(root: &Path, config: &Config, agent: &str, subject: &Subject) -> io::Result<String> {
+    let dirs = prompt_dirs(root);
+    let task_type = subject
+        .task
+        .and_then(|task| task.field("type"))
+        .map(|field| field.value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let (template, name) = match find_template(&dirs, agent, &task_type) {
+        Some(path) => (fs::read_to_string(&path)?, relative(root, &path)),
+        None => (BUILT_IN.to_string(), "built-in template".to_string()),
+    };
+    let values = values(root, config, agent, subject);
+
+    let rendered = expand(&template, &values, &dirs, 0)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", name, e)))?;
+    Ok(rendered.trim().to_string())
+}
+
+// `cdd prompt TASK`: print the prompt `cdd run` would send
+pub fn prompt_command(root: &Path, config: &Config, agent: &str, query: &str) -> io::Result<()> {
+    let task = task::find(root, &config.tasks_dir(root), query)?;
+    let content = fs::read_to_string(&task.path)?;
+    let subject = Subject {
+        task: Some(&task),
+        content: &content,
+    };
+    println!("{}", render(root, config, agent, &subject)?);
+    Ok(())
+}
+
+// Project templates first, so a repository can override a user's
+fn prompt_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![root.join(".context/prompts")];
+    if let Some(user) =
+        config::user_config_path().and_then(|path| Some(path.parent()?.join("prompts")))
+    {
+        dirs.push(user);
+    }
+    dirs
+}
+
+fn find_template(dirs: &[PathBuf], agent: &str, task_type: &str) -> Option<PathBuf> {
+    let mut names = Vec::new();
+    if !agent.is_empty() && !task_type.is_empty() {
+        names.push(format!("{}.{}", agent, task_type));
+    }
+    if !task_type.is_empty() {
+        names.push(task_type.to_string());
+    }
+    if !agent.is_empty() {
+        names.push(agent.to_string());
+    }
+    names.push("default".to_string());
+
+    names.iter().find_map(|name| find_file(dirs, name))
+}
+
+fn find_file(dirs: &[PathBuf], name: &str) -> Option<PathBuf> {
+    dirs.iter()
+        .map(|dir| dir.join(format!("{}.md", name)))
+        .find(|path| path.is_file())
+}
+
+fn values(
+    root: &Path,
+    config: &Config,
+    agent: &str,
+    subject: &Subject,
+) -> Vec<(&'static str, String)> {
+    let task = subject.task;
+    let text = |value: Option<&String>| value.cloned().unwrap_or_default();
+
+    vec![
+        ("prompt_prefix", config.run.prompt_prefix.clone()),
+        ("agent", agent.to_string()),
+        ("task.id", task.map(Task::id).unwrap_or_default()),
+        (
+            "task.title",
+            text(task.and_then(|task| task.title.as_ref())),
+        ),
+        (
+            "task.project",
+            text(task.and_then(|task| task.project.as_ref())),
+        ),
+        (
+            "task.status",
+            task.and_then(|task| status::current(task).ok())
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        (
+            "task.type",
+            task.and_then(|task| task.field("type"))
+                .map(|field| field.value.clone())
+                .unwrap_or_default(),
+        ),
+        (
+            "task.path",
+            task.map(|task| relative(root, &task.path))
+                .unwrap_or_default(),
+        ),
+        (
+            "task.body",
+            task::split_frontmatter(subject.content)
+                .1
+                .trim()
+                .to_string(),
+        ),
+        ("task.content", subject.content.trim_end().to_string()),
+        (
+            "rules",
+            scoped_rules(root, subject.content)
+                .iter()
+                .map(|rule| rule.body.trim())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        ),
+        (
+            "lessons",
+            task.map(|task| lessons(config, root, task))
+                .unwrap_or_default(),
+        ),
+        ("git.branch", git_branch(root)),
+    ]
+}
+
+// Expand variables, conditionals and includes in `template`
+fn expand(
+    template: &str,
+    values: &[(&str, String)],
+    dirs: &[PathBuf],
+    depth: usize,
+) -> Result<String, String> {
+    let template = strip_standalone(template);
+    let mut output = String::new();
+    let mut rest = template.as_str();
+    // One entry per open {{#if}}: its condition and whether the branch being
+    // read is kept
+    let mut branches: Vec<(bool, bool)> = Vec::new();
+
+    while let Some(start) = rest.find("{{") {
+        let keep = branches.iter().all(|(_, kept)| *kept);
+        if keep {
+            output.push_str(&rest[..start]);
+        }
+        let Some(end) = rest[start..].find("}}") else {
+            return Err("unclosed {{".to_string());
+        };
+        let tag = rest[start + 2..start + end].trim();
+        rest = &rest[start + end + 2..];
+
+        if let Some(name) = tag.strip_prefix("#if ") {
+            let value = lookup(values, name.trim())?;
+            branches.push((!value.trim().is_empty(), !value.trim().is_empty()));
+        } else if tag == "else" {
+            let Some((condition, kept)) = branches.last_mut() else {
+                return Err("{{else}} without {{#if}}".to_string());
+            };
+            *kept = !*condition;
+        } else if tag == "/if" {
+            if branches.pop().is_none() {
+                return Err("{{/if}} without {{#if}}".to_string());
+            }
+        } else if let Some(name) = tag.strip_prefix('>') {
+            if !keep {
+                continue;
+            }
+            let name = name.trim();
+            if depth >= MAX_INCLUDE_DEPTH {
+                return Err(format!("includes nested too deep at '{}'", name));
+            }
+            let Some(path) = find_file(dirs, name) else {
+                return Err(format!("no template '{}.md' to include", name));
+            };
+            let included = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            output.push_str(&expand(included.trim_end(), values, dirs, depth + 1)?);
+        } else if keep {
+            output.push_str(lookup(values, tag)?);
+        } else {
+            // Still catch typos in branches that aren't taken
+            lookup(values, tag)?;
+        }
+    }
+
+    if !branches.is_empty() {
+        return Err("{{#if}} without {{/if}}".to_string());
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+// A line holding only {{#if}}, {{else}} or {{/if}} leaves no blank line
+fn strip_standalone(template: &str) -> String {
+    template
+        .split_inclusive('\n')
+        .map(|line| {
+            let tag = line.trim();
+            let control = tag
+                .strip_prefix("{{")
+                .and_then(|tag| tag.strip_suffix("}}"))
+                .filter(|inner| !inner.contains("{{"))
+                .map(str::trim)
+                .is_some_and(|inner| {
+                    inner.starts_with("#if ") || inner == "else" || inner == "/if"
+                });
+            if control {
+                tag
+            } else {
+                line
+            }
+        })
+        .collect()
+}
+
+fn lookup<'a>(values: &'a [(&str, String)], name: &str) -> Result<&'a str, String> {
+    values
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.as_str())
+        .ok_or_else(|| {
+            format!(
+                "unknown variable '{}' (expected one of {})",
+                name,
+                VARIABLES.join(", ")
+            )
+        })
+}
+
+// Rules whose globs match a file in the File System Diff of `content`,
+// which may hold several tasks
+pub fn scoped_rules(root: &Path, content: &str) -> Vec<Rule> {
+    let files: Vec<String> = file_system_diff::parse_file_system_diff(content)
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| entry.path)
+        .collect();
+    rules::matching(rules::load(root), &files)
+}
+
+// Lessons Learned written in the project's finished tasks, oldest first
+fn lessons(config: &Config, root: &Path, current: &Task) -> String {
+    let Ok(mut tasks) = task::load_all(&config.tasks_dir(root)) else {
+        return String::new();
+    };
+    tasks.sort_by_key(|task| (task.created, task.id()));
+
+    let mut output = Vec::new();
+    for task in tasks.iter().filter(|task| {
+        task.path != current.path
+            && task.project == current.project
+            && status::current(task).ok() == Some("done")
+    }) {
+        let Some(section) = task.section("Lessons Learned") else {
+            continue;
+        };
+        let lessons = task::strip_comments(&section.content);
+        // Skip the template's "<...>" placeholders
+        let written = lessons.lines().any(|line| {
+            let line = line.trim().trim_start_matches("- ").trim();
+            !line.is_empty() && !line.starts_with('<')
+        });
+        if written {
+            let heading = match &task.title {
+                Some(title) => format!("{}: {}", task.id(), title),
+                None => task.id(),
+            };
+            output.push(format!("### {}\n\n{}", heading, lessons.trim()));
+        }
+    }
+    output.join("\n\n")
+}
+
+fn git_branch(root: &Path) -> String {
+    Command::new("git")
+        .args(["branch", "--show-current"])
+        .current_dir(root)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,200 @@
+// Rules in .context/_reference/rules can declare the files they apply to
+// in their frontmatter:
+//
+//     ---
+//     globs: ["**/*.rs", "Cargo.toml"]
+//     ---
+//
+// The launch prompt carries the rules whose globs match a file in the task's
+// File System Diff, and Cursor gets the same globs in its .mdc rules. A
+// pattern without a "/" matches the file name in any directory.
+
+use crate::task;
+use std::fs;
+use std::path::Path;
+
+pub struct Rule {
+    pub name: String,
+    pub globs: Vec<String>,
+    // The rule without its frontmatter
+    pub body: String,
+}
+
+pub fn parse(name: &str, contents: &str) -> Rule {
+    let (frontmatter, body) = task::split_frontmatter(contents);
+    Rule {
+        name: name.to_string(),
+        globs: frontmatter.map(globs).unwrap_or_default(),
+        body: body.trim_start_matches('\n').to_string(),
+    }
+}
+
+// The project's rules, in name order
+pub fn load(root: &Path) -> Vec<Rule> {
+    let Ok(entries) = fs::read_dir(root.join(".context/_reference/rules")) else {
+        return Vec::new();
+    };
+    let mut rules: Vec<Rule> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| {
+            let contents = fs::read_to_string(&path).ok()?;
+            let name = path.file_name()?.to_string_lossy().to_string();
+            Some(parse(&name, &contents))
+        })
+        .collect();
+    rules.sort_by(|a, b| a.name.cmp(&b.name));
+    rules
+}
+
+// Rules scoped to at least one of `files`; rules without globs aren't
+// matched, agents load those through their own configuration
+pub fn matching(rules: Vec<Rule>, files: &[String]) -> Vec<Rule> {
+    rules
+        .into_iter()
+        .filter(|rule| {
+            rule.globs
+                .iter()
+                .any(|glob| files.iter().any(|file| glob_match(glob, file)))
+        })
+        .collect()
+}
+
+// `*` and `?` stay within a path segment, `**` spans any number of them
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("./");
+    let path = path.trim_start_matches("./");
+    if !pattern.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return segment_match(pattern.as_bytes(), name.as_bytes());
+    }
+
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path)) => {
+                segment_match(first.as_bytes(), name.as_bytes()) && segments_match(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+fn segment_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| segment_match(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && segment_match(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && segment_match(rest, &name[1..]),
+    }
+}
+
+// `globs: ["a", "b"]`, `globs: a, b` or a block list of "- a" lines
+fn globs(frontmatter: &str) -> Vec<String> {
+    let mut lines = frontmatter.lines();
+    let Some(first) = lines.find_map(|line| line.strip_prefix("globs:")) else {
+        return Vec::new();
+    };
+
+    let mut values = vec![first.to_string()];
+    if first.trim().is_empty() {
+        values.extend(
+            lines
+                .take_while(|line| line.starts_with([' ', '-']))
+                .map(str::to_string),
+        );
+    }
+    values
+        .iter()
+        .flat_map(|value| {
+            value
+                .trim()
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .split(',')
+                .map(|glob| {
+                    glob.trim()
+                        .trim_start_matches("- ")
+                        .trim_matches(['"', '\''])
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|glob| !glob.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_star_spans_any_number_of_directories() {
+        assert!(glob_match("**/*.rs", "main.rs"));
+        assert!(glob_match("**/*.rs", "src/main.rs"));
+        assert!(glob_match("**/*.rs", "src/profiles/claude.rs"));
+        assert!(glob_match("src/**/mod.rs", "src/mod.rs"));
+        assert!(glob_match("src/**/mod.rs", "src/profiles/mod.rs"));
+        assert!(!glob_match("**/*.rs", "src/main.rsx"));
+        assert!(!glob_match("src/**/mod.rs", "tests/mod.rs"));
+    }
+
+    #[test]
+    fn star_stays_within_a_segment() {
+        assert!(glob_match("src/*.rs", "src/main.rs"));
+        assert!(!glob_match("src/*.rs", "src/profiles/claude.rs"));
+        assert!(glob_match("src/?ain.rs", "src/main.rs"));
+        assert!(!glob_match("src/?.rs", "src/main.rs"));
+    }
+
+    #[test]
+    fn bare_names_match_in_any_directory() {
+        assert!(glob_match("Cargo.toml", "Cargo.toml"));
+        assert!(glob_match("Cargo.toml", "crates/core/Cargo.toml"));
+        assert!(glob_match("*.just", "justfiles/dev/fmt.just"));
+        assert!(glob_match("justfile", "./justfile"));
+        assert!(!glob_match("Cargo.toml", "Cargo.lock"));
+    }
+
+    #[test]
+    fn reads_globs_from_frontmatter() {
+        let rule = parse(
+            "rs.md",
+            "---\nglobs: [\"**/*.rs\", 'Cargo.toml']\n---\n\n# Rust\n",
+        );
+        assert_eq!(rule.globs, ["**/*.rs", "Cargo.toml"]);
+        assert_eq!(rule.body, "# Rust\n");
+
+        let rule = parse(
+            "rs.md",
+            "---\nglobs:\n  - \"**/*.rs\"\n  - justfile\n---\n# Rust\n",
+        );
+        assert_eq!(rule.globs, ["**/*.rs", "justfile"]);
+    }
+
+    #[test]
+    fn a_rule_without_frontmatter_has_no_globs() {
+        let rule = parse("rs.md", "# Rust\n\n---\n");
+        assert!(rule.globs.is_empty());
+        assert_eq!(rule.body, "# Rust\n\n---\n");
+    }
+
+    #[test]
+    fn matches_rules_by_the_files_touched() {
+        let rules = vec![
+            parse("rs.md", "---\nglobs: **/*.rs\n---\n"),
+            parse("justfiles.md", "---\nglobs: justfile, *.just\n---\n"),
+            parse("general.md", "# Everywhere\n"),
+        ];
+        let matched = matching(rules, &["src/lib.rs".to_string()]);
+        let names: Vec<&str> = matched.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(names, ["rs.md"]);
+    }
+}
@@ -0,0 +1,345 @@
+// Task status lifecycle, kept in the `status` frontmatter key:
+//
+//     draft -> ready -> in-progress -> in-review -> done
+//
+// Unfinished tasks can also be abandoned. done and abandoned are final
+// unless --force is given.
+
+use crate::config::Config;
+use crate::files::relative;
+use crate::task::{self, Task};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+
+pub const STATUSES: &[&str] = &[
+    "draft",
+    "ready",
+    "in-progress",
+    "in-review",
+    "done",
+    "abandoned",
+];
+
+// Values older task files use for the same states
+const ALIASES: &[(&str, &str)] = &[
+    ("todo", "ready"),
+    ("wip", "in-progress"),
+    ("in_progress", "in-progress"),
+    ("review", "in-review"),
+    ("completed", "done"),
+    ("complete", "done"),
+    ("cancelled", "abandoned"),
+];
+
+// Tasks written before the field existed have no status and count as ready
+const DEFAULT_STATUS: &str = "ready";
+
+// Where each status may move to
+const TRANSITIONS: &[(&str, &[&str])] = &[
+    ("draft", &["ready", "abandoned"]),
+    ("ready", &["in-progress", "draft", "abandoned"]),
+    ("in-progress", &["in-review", "done", "ready", "abandoned"]),
+    ("in-review", &["done", "in-progress", "abandoned"]),
+    ("done", &[]),
+    ("abandoned", &[]),
+];
+
+// The status name for `value`, accepting the older spellings
+pub fn canonical(value: &str) -> Option<&'static str> {
+    let value = value.trim().to_ascii_lowercase();
+    STATUSES
+        .iter()
+        .find(|status| **status == value)
+        .copied()
+        .or_else(|| {
+            ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == value)
+                .map(|(_, status)| *status)
+        })
+}
+
+// Status of `task`, or an error for a value outside the lifecycle
+pub fn current(task: &Task) -> Result<&'static str, String> {
+    match task.status.as_deref() {
+        None => Ok(DEFAULT_STATUS),
+        Some(value) => canonical(value).ok_or_else(|| {
+            format!(
+                "unknown status '{}' (expected one of {})",
+                value,
+                STATUSES.join(", ")
+            )
+        }),
+    }
+}
+
+fn next(from: &str) -> &'static [&'static str] {
+    TRANSITIONS
+        .iter()
+        .find(|(status, _)| *status == from)
+        .map(|(_, next)| *next)
+        .unwrap_or_default()
+}
+
+pub fn can_move(from: &str, to: &str) -> bool {
+    next(from).contains(&to)
+}
+
+// Rewrite the status and bump `updated`
+fn write_status(path: &Path, status: &str) -> io::Result<()> {
+    let content = fs::read_to_string(path)?;
+    let today = chrono::Local::now().date_naive();
+    let content = task::set_field(&content, "status", status);
+    let content = task::set_field(&content, "updated", &format!("\"{}\"", today));
+    fs::write(path, content)
+}
+
+// Move `task` to `to`, refusing transitions the lifecycle doesn't allow
+pub fn transition(root: &Path, task: &Task, to: &str, force: bool) -> io::Result<()> {
+    let Some(to) = canonical(to) else {
+        return Err(invalid_input(format!(
+            "unknown status '{}' (expected one of {})",
+            to,
+            STATUSES.join(", ")
+        )));
+    };
+    let from = current(task).map_err(invalid_input)?;
+
+    if from == to {
+        println!("• {} is already {}", task.id(), to);
+        return Ok(());
+    }
+    if !force && !can_move(from, to) {
+        let allowed = match next(from) {
+            [] => "none, use --force to override".to_string(),
+            next => next.join(", "),
+        };
+        return Err(invalid_input(format!(
+            "{} is {} and can't move to {} (allowed: {})",
+            task.id(),
+            from,
+            to,
+            allowed
+        )));
+    }
+
+    write_status(&task.path, to)?;
+    println!(
+        "✓ {}: {} → {}  ({})",
+        task.id(),
+        from,
+        to,
+        relative(root, &task.path)
+    );
+    Ok(())
+}
+
+// Mark `task` done for `cdd done` and the picker, going through the statuses
+// in between (e.g. ready → in-progress → done) the way the lifecycle allows.
+// `force` also finishes an abandoned task.
+pub fn complete(root: &Path, task: &Task, force: bool) -> io::Result<()> {
+    let from = current(task).map_err(invalid_input)?;
+    if from == "done" {
+        println!("• {} is already done", task.id());
+        return Ok(());
+    }
+
+    // Shortest route through TRANSITIONS
+    let mut routes: Vec<Vec<&str>> = vec![vec![from]];
+    let mut seen = vec![from];
+    let mut index = 0;
+    let route = loop {
+        let Some(route) = routes.get(index).cloned() else {
+            if force {
+                break vec![from, "done"];
+            }
+            return Err(invalid_input(format!(
+                "{} is {} and can't be marked done (use --force to override)",
+                task.id(),
+                from
+            )));
+        };
+        index += 1;
+        let last = route[route.len() - 1];
+        if last == "done" {
+            break route;
+        }
+        for step in next(last) {
+            if !seen.contains(step) {
+                seen.push(step);
+                let mut longer = route.clone();
+                longer.push(step);
+                routes.push(longer);
+            }
+        }
+    };
+
+    write_status(&task.path, "done")?;
+    println!(
+        "✓ {}: {}  ({})",
+        task.id(),
+        route.join(" → "),
+        relative(root, &task.path)
+    );
+    Ok(())
+}
+
+// Called when an agent is launched for `task`: ready and in-review tasks
+// become in-progress, anything else is left alone
+pub fn start_on_launch(task: &Task) -> io::Result<()> {
+    let Ok(from) = current(task) else {
+        return Ok(());
+    };
+    if from == "in-progress" || !can_move(from, "in-progress") {
+        return Ok(());
+    }
+
+    write_status(&task.path, "in-progress")?;
+    println!("• {}: {} → in-progress", task.id(), from);
+    Ok(())
+}
+
+// `cdd status [TASK [STATUS]]`, `cdd start TASK` and `cdd done TASK`
+pub fn status_command(
+    root: &Path,
+    config: &Config,
+    command: &str,
+    args: &[String],
+) -> io::Result<()> {
+    let tasks_dir = config.tasks_dir(root);
+    let force = args.iter().any(|arg| arg == "--force");
+    let positional: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--force")
+        .collect();
+
+    match (command, positional.as_slice()) {
+        ("status", []) => {
+            print_statuses(root, &task::load_all(&tasks_dir)?);
+            Ok(())
+        }
+        ("status", [query]) => {
+            let task = task::find(root, &tasks_dir, query)?;
+            let status = current(&task).map_err(invalid_input)?;
+            let allowed = match next(status) {
+                [] => "none".to_string(),
+                next => next.join(", "),
+            };
+            println!("{} is {} (next: {})", task.id(), status, allowed);
+            Ok(())
+        }
+        ("status", [query, to]) => {
+            let task = task::find(root, &tasks_dir, query)?;
+            transition(root, &task, to, force)
+        }
+        ("start", [query]) => {
+            let task = task::find(root, &tasks_dir, query)?;
+            transition(root, &task, "in-progress", force)
+        }
+        ("done", [query]) => {
+            let task = task::find(root, &tasks_dir, query)?;
+            complete(root, &task, force)
+        }
+        _ => {
+            eprintln!("Usage: cdd status [TASK [STATUS]] [--force]");
+            eprintln!("       cdd start TASK | cdd done TASK");
+            eprintln!("Statuses: {}", STATUSES.join(", "));
+            process::exit(1);
+        }
+    }
+}
+
+// Every task with its status, in lifecycle order
+fn print_statuses(root: &Path, tasks: &[Task]) {
+    if tasks.is_empty() {
+        println!("No tasks found.");
+        return;
+    }
+
+    let mut rows: Vec<(usize, String, String, String)> = tasks
+        .iter()
+        .map(|task| {
+            let (order, status) = match current(task) {
+                Ok(status) => (
+                    STATUSES.iter().position(|s| *s == status).unwrap_or(0),
+                    status.to_string(),
+                ),
+                Err(_) => (
+                    STATUSES.len(),
+                    format!("{}?", task.status.clone().unwrap_or_default()),
+                ),
+            };
+            let title = task
+                .title
+                .clone()
+                .unwrap_or_else(|| relative(root, &task.path));
+            (order, status, task.id(), title)
+        })
+        .collect();
+    rows.sort();
+
+    let width = rows.iter().map(|row| row.2.len()).max().unwrap_or(0);
+    for (_, status, id, title) in rows {
+        println!("{:width$}  {:11}  {}", id, status, title, width = width);
+    }
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ready_task(root: &Path, id: &str) -> Task {
+        let dir = root.join(".context/tasks");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}-demo.md", id));
+        let content = format!(
+            "---\ntask_id: {}\ntitle: Demo\nstatus: ready\n---\n\n# Demo\n",
+            id
+        );
+        fs::write(&path, &content).unwrap();
+        Task::parse(&path, &content).unwrap()
+    }
+
+    fn status_of(task: &Task) -> String {
+        let content = fs::read_to_string(&task.path).unwrap();
+        let task = Task::parse(&task.path, &content).unwrap();
+        current(&task).unwrap().to_string()
+    }
+
+    #[test]
+    fn done_command_completes_a_ready_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let task = ready_task(dir.path(), "TASK_001");
+        let args = ["TASK_001".to_string()];
+        status_command(dir.path(), &Config::default(), "done", &args).unwrap();
+        assert_eq!(status_of(&task), "done");
+    }
+
+    #[test]
+    fn picker_completes_a_ready_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let task = ready_task(dir.path(), "TASK_001");
+        complete(dir.path(), &task, false).unwrap();
+        assert_eq!(status_of(&task), "done");
+    }
+
+    #[test]
+    fn abandoned_tasks_need_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let task = ready_task(dir.path(), "TASK_001");
+        transition(dir.path(), &task, "abandoned", false).unwrap();
+        let content = fs::read_to_string(&task.path).unwrap();
+        let task = Task::parse(&task.path, &content).unwrap();
+
+        assert!(complete(dir.path(), &task, false).is_err());
+        complete(dir.path(), &task, true).unwrap();
+        assert_eq!(status_of(&task), "done");
+    }
+}
@@ -0,0 +1,659 @@
+// Typed view of a task file as described by _reference/templates/TASK.md:
+// a frontmatter block followed by "## " sections.
+//
+//     ---
+//     task_id: TASK_001
+//     title: Add user authentication
+//     project: CORE_001
+//     created: "2025-01-05"
+//     ---
+//
+//     # TASK_001: Add user authentication
+//
+//     ## Summary
+//     ...
+
+use crate::file_system_diff::{self, DiffEntry};
+use crate::files::collect_files;
+use chrono::NaiveDate;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Sections every task is expected to have, in template order
+pub const SECTIONS: &[&str] = &[
+    "Summary",
+    "Goals",
+    "File System Diff",
+    "Lessons Learned",
+    "Validation",
+];
+
+// Most goals a task should have (see cdd-review-tasks)
+pub const MAX_GOALS: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub path: PathBuf,
+    pub task_id: Option<String>,
+    pub title: Option<String>,
+    pub project: Option<String>,
+    pub created: Option<NaiveDate>,
+    pub updated: Option<NaiveDate>,
+    pub status: Option<String>,
+    // Every frontmatter entry in file order, including the keys above
+    pub fields: Vec<Field>,
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub key: String,
+    // Unquoted, without trailing comments; indented continuation lines are
+    // kept as written
+    pub value: String,
+    pub line: usize,
+    // Where the value starts on its line
+    pub column: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub title: String,
+    // 1-based line of the "## " heading
+    pub line: usize,
+    // Text between this heading and the next one
+    pub content: String,
+}
+
+// A problem at a specific place in a task file (1-based line and column)
+#[derive(Debug, Clone)]
+pub struct TaskError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    pub source_line: String,
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.path.display(),
+            self.line,
+            self.column,
+            self.message
+        )?;
+        if !self.source_line.is_empty() {
+            let gutter = self.line.to_string().len();
+            write!(
+                f,
+                "\n{:gutter$} |\n{} | {}\n{:gutter$} | {}^",
+                "",
+                self.line,
+                self.source_line,
+                "",
+                " ".repeat(self.column.saturating_sub(1)),
+                gutter = gutter
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Task {
+    pub fn parse(path: &Path, content: &str) -> Result<Task, TaskError> {
+        let lines: Vec<&str> = content.lines().collect();
+        let error = |line: usize, column: usize, message: String| TaskError {
+            path: path.to_path_buf(),
+            line,
+            column,
+            message,
+            source_line: lines.get(line.wrapping_sub(1)).unwrap_or(&"").to_string(),
+        };
+
+        if lines.first().map(|line| line.trim_end()) != Some("---") {
+            return Err(error(
+                1,
+                1,
+                "expected frontmatter starting with '---'".to_string(),
+            ));
+        }
+        let Some(end) = split_frontmatter(content)
+            .0
+            .map(|frontmatter| frontmatter.lines().count() + 1)
+        else {
+            return Err(error(
+                1,
+                1,
+                "frontmatter is not closed with '---'".to_string(),
+            ));
+        };
+
+        let mut fields: Vec<Field> = Vec::new();
+        for (index, line) in lines.iter().enumerate().take(end).skip(1) {
+            let number = index + 1;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            // Indented lines (block lists, folded text) belong to the previous key
+            if line.starts_with([' ', '\t']) {
+                let Some(previous) = fields.last_mut() else {
+                    return Err(error(number, 1, "indented line before any key".to_string()));
+                };
+                if !previous.value.is_empty() {
+                    previous.value.push('\n');
+                }
+                previous.value.push_str(trimmed);
+                continue;
+            }
+
+            let Some((key, raw_value)) = line.split_once(':') else {
+                return Err(error(number, 1, "expected 'key: value'".to_string()));
+            };
+            let key = key.trim_end();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(error(number, 1, format!("invalid key '{}'", key)));
+            }
+            if fields.iter().any(|field| field.key == key) {
+                return Err(error(number, 1, format!("duplicate key '{}'", key)));
+            }
+
+            let value_column = key.len() + 2 + raw_value.len() - raw_value.trim_start().len();
+            let value =
+                scalar_value(raw_value).map_err(|message| error(number, value_column, message))?;
+            fields.push(Field {
+                key: key.to_string(),
+                value,
+                line: number,
+                column: value_column,
+            });
+        }
+
+        let sections =
+            parse_sections(&lines, end + 1).map_err(|(line, message)| error(line, 1, message))?;
+        let mut task = Task {
+            path: path.to_path_buf(),
+            task_id: None,
+            title: None,
+            project: None,
+            created: None,
+            updated: None,
+            status: None,
+            fields,
+            sections,
+        };
+
+        let date = |key: &str| -> Result<Option<NaiveDate>, TaskError> {
+            let Some(field) = task.field(key).filter(|field| !field.value.is_empty()) else {
+                return Ok(None);
+            };
+            NaiveDate::parse_from_str(&field.value, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| {
+                    error(
+                        field.line,
+                        field.column,
+                        format!("'{}' must be a date like \"2025-01-31\"", key),
+                    )
+                })
+        };
+        let created = date("created")?;
+        let updated = date("updated")?;
+
+        task.created = created;
+        task.updated = updated;
+        task.task_id = task.text("task_id");
+        task.title = task.text("title");
+        task.project = task.text("project");
+        task.status = task.text("status");
+
+        Ok(task)
+    }
+
+    pub fn field(&self, key: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.key == key)
+    }
+
+    // Non-empty value of a frontmatter key
+    fn text(&self, key: &str) -> Option<String> {
+        self.field(key)
+            .map(|field| field.value.clone())
+            .filter(|value| !value.is_empty())
+    }
+
+    pub fn section(&self, title: &str) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|section| section.title.eq_ignore_ascii_case(title))
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.list("tags")
+    }
+
+    // Ids of the tasks this one builds on
+    pub fn depends_on(&self) -> Vec<String> {
+        self.list("depends_on")
+    }
+
+    // `key: [a, b]`, `key: a, b` or a block list of "- a" lines
+    fn list(&self, key: &str) -> Vec<String> {
+        let Some(field) = self.field(key) else {
+            return Vec::new();
+        };
+        field
+            .value
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split([',', '\n'])
+            .map(|item| {
+                item.trim()
+                    .trim_start_matches("- ")
+                    .trim_matches(['"', '\''])
+                    .to_string()
+            })
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    pub fn file_system_diff(&self) -> Vec<DiffEntry> {
+        self.section("File System Diff")
+            .map(|section| {
+                file_system_diff::parse_file_system_diff(&format!(
+                    "## File System Diff\n{}",
+                    section.content
+                ))
+            })
+            .unwrap_or_default()
+    }
+
+    // task_id from the frontmatter, or the TASK_NNN prefix of the file name
+    pub fn id(&self) -> String {
+        match &self.task_id {
+            Some(id) => id.clone(),
+            None => file_id(&self.path),
+        }
+    }
+}
+
+// `content` with `key` set to `value` in its frontmatter: the existing line
+// (and any continuation lines) is replaced, otherwise the key is added at the
+// end of the frontmatter. Content without frontmatter is returned unchanged.
+pub fn set_field(content: &str, key: &str, value: &str) -> String {
+    let Some(frontmatter) = split_frontmatter(content).0 else {
+        return content.to_string();
+    };
+    let close = frontmatter.lines().count() + 1;
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    let line = format!("{}: {}", key, value);
+    let existing = (1..close).find(|index| {
+        let current = &lines[*index];
+        !current.starts_with([' ', '\t'])
+            && current
+                .split_once(':')
+                .is_some_and(|(name, _)| name.trim_end() == key)
+    });
+    match existing {
+        Some(index) => {
+            let continuation = lines[index + 1..close]
+                .iter()
+                .take_while(|line| line.starts_with([' ', '\t']))
+                .count();
+            lines.splice(index..index + 1 + continuation, [line]);
+        }
+        None => lines.insert(close, line),
+    }
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+// The frontmatter between the `---` lines, each line keeping its newline,
+// and the body after the closing line. Content that doesn't open and close a
+// frontmatter block is all body.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let Some((first, rest)) = content.split_once('\n') else {
+        return (None, content);
+    };
+    if first.trim_end() != "---" {
+        return (None, content);
+    }
+
+    let mut end = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..end]), &rest[end + line.len()..]);
+        }
+        end += line.len();
+    }
+    (None, content)
+}
+
+// TASK_007-add-auth.md -> TASK_007
+pub fn file_id(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    match stem.split_once('-') {
+        Some((id, _)) => id.to_string(),
+        None => stem,
+    }
+}
+
+// The task named by `query`: a path to a task file, or a task id
+// (case-insensitive). Ids shared by several tasks are an error.
+pub fn find(root: &Path, tasks_dir: &Path, query: &str) -> io::Result<Task> {
+    let path = root.join(query);
+    if path.is_file() {
+        let content = fs::read_to_string(&path)?;
+        return Task::parse(&path, &content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()));
+    }
+
+    let mut matches: Vec<Task> = load_all(tasks_dir)?
+        .into_iter()
+        .filter(|task| task.id().eq_ignore_ascii_case(query))
+        .collect();
+    match matches.len() {
+        0 => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no task with id or path '{}'", query),
+        )),
+        1 => Ok(matches.remove(0)),
+        count => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is used by {} tasks, run 'cdd ids fix' first",
+                query, count
+            ),
+        )),
+    }
+}
+
+// Every task below `tasks_dir` that parses, in path order
+pub fn load_all(tasks_dir: &Path) -> io::Result<Vec<Task>> {
+    let mut files = Vec::new();
+    collect_files(tasks_dir, &mut files)?;
+    files.sort();
+
+    Ok(files
+        .iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| {
+            let content = fs::read_to_string(path).ok()?;
+            Task::parse(path, &content).ok()
+        })
+        .collect())
+}
+
+// Drop <!-- ... --> blocks (the template's authoring notes)
+pub fn strip_comments(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<!--") {
+        output.push_str(&rest[..start]);
+        match rest[start..].find("-->") {
+            Some(end) => rest = &rest[start + end + "-->".len()..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+// Strip quotes or a trailing "# comment" from a frontmatter value. Quoted
+// values are read as YAML reads them: backslash escapes inside double
+// quotes, and '' for a quote inside single quotes.
+fn scalar_value(raw: &str) -> Result<String, String> {
+    let value = raw.trim();
+
+    let (unquoted, after) = if let Some(rest) = value.strip_prefix('"') {
+        double_quoted(rest)?
+    } else if let Some(rest) = value.strip_prefix('\'') {
+        single_quoted(rest)?
+    } else {
+        return Ok(match value.find(" #") {
+            Some(index) => value[..index].trim_end().to_string(),
+            None => value.to_string(),
+        });
+    };
+
+    let after = after.trim();
+    if !after.is_empty() && !after.starts_with('#') {
+        return Err(format!("unexpected text after closing quote: '{}'", after));
+    }
+    Ok(unquoted)
+}
+
+// The value up to the closing quote, and what follows it
+fn double_quoted(rest: &str) -> Result<(String, &str), String> {
+    let mut value = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &rest[index + 1..])),
+            '\\' => {
+                let Some((_, escaped)) = chars.next() else {
+                    break;
+                };
+                value.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    '"' | '\\' | '/' | ' ' => escaped,
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid escape '\\u{}'", hex))?
+                    }
+                    other => return Err(format!("unknown escape '\\{}'", other)),
+                });
+            }
+            c => value.push(c),
+        }
+    }
+    Err("unterminated \" quote".to_string())
+}
+
+fn single_quoted(rest: &str) -> Result<(String, &str), String> {
+    let mut value = String::new();
+    let mut remaining = rest;
+    while let Some(close) = remaining.find('\'') {
+        value.push_str(&remaining[..close]);
+        remaining = &remaining[close + 1..];
+        match remaining.strip_prefix('\'') {
+            Some(after) => {
+                value.push('\'');
+                remaining = after;
+            }
+            None => return Ok((value, remaining)),
+        }
+    }
+    Err("unterminated ' quote".to_string())
+}
+
+// Split the body (starting at line index `start`) on "## " headings that are
+// not inside code fences
+fn parse_sections(lines: &[&str], start: usize) -> Result<Vec<Section>, (usize, String)> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut in_fence = false;
+
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let number = index + 1;
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+
+        match line.strip_prefix("## ").filter(|_| !in_fence) {
+            Some(title) => {
+                let title = title.trim().to_string();
+                let known = SECTIONS
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(&title));
+                if known
+                    && sections
+                        .iter()
+                        .any(|section| section.title.eq_ignore_ascii_case(&title))
+                {
+                    return Err((number, format!("duplicate '## {}' section", title)));
+                }
+                sections.push(Section {
+                    title,
+                    line: number,
+                    content: String::new(),
+                });
+            }
+            None => {
+                if let Some(section) = sections.last_mut() {
+                    section.content.push_str(line);
+                    section.content.push('\n');
+                }
+            }
+        }
+    }
+
+    if in_fence {
+        return Err((lines.len(), "code fence is not closed".to_string()));
+    }
+
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frontmatter_splits_from_the_body() {
+        assert_eq!(
+            split_frontmatter("---\ntitle: Demo\n---\n\n# Demo\n"),
+            (Some("title: Demo\n"), "\n# Demo\n")
+        );
+        assert_eq!(split_frontmatter("---\r\n---\r\nbody"), (Some(""), "body"));
+        assert_eq!(
+            split_frontmatter("---\ntitle: Demo\n---"),
+            (Some("title: Demo\n"), "")
+        );
+    }
+
+    #[test]
+    fn content_without_closed_frontmatter_is_all_body() {
+        assert_eq!(
+            split_frontmatter("# Demo\n\n---\n"),
+            (None, "# Demo\n\n---\n")
+        );
+        assert_eq!(
+            split_frontmatter("---\ntitle: Demo\n"),
+            (None, "---\ntitle: Demo\n")
+        );
+        assert_eq!(split_frontmatter("---"), (None, "---"));
+    }
+
+    #[test]
+    fn dashes_inside_a_line_do_not_close_the_frontmatter() {
+        assert_eq!(
+            split_frontmatter("---\ntitle: a --- b\n----\n---\nbody"),
+            (Some("title: a --- b\n----\n"), "body")
+        );
+    }
+
+    // Line, column and message of the error `Task::parse` gives for `content`
+    fn parse_error(content: &str) -> (usize, usize, String) {
+        let error = Task::parse(Path::new("TASK.md"), content).unwrap_err();
+        (error.line, error.column, error.message)
+    }
+
+    #[test]
+    fn a_valid_task_parses() {
+        let task = Task::parse(
+            Path::new("TASK.md"),
+            "---\ntitle: Demo\nstatus: ready\n---\n\n## Summary\nText\n\n## Goals\n- One\n",
+        )
+        .unwrap();
+        assert_eq!(task.title.as_deref(), Some("Demo"));
+        assert_eq!(task.section("goals").map(|section| section.line), Some(9));
+    }
+
+    #[test]
+    fn frontmatter_errors_point_at_their_line() {
+        assert_eq!(
+            parse_error("# Demo\n"),
+            (1, 1, "expected frontmatter starting with '---'".to_string())
+        );
+        assert_eq!(
+            parse_error("---\ntitle: Demo\n"),
+            (1, 1, "frontmatter is not closed with '---'".to_string())
+        );
+        assert_eq!(
+            parse_error("---\ntitle: Demo\nno colon here\n---\n"),
+            (3, 1, "expected 'key: value'".to_string())
+        );
+        assert_eq!(
+            parse_error("---\ntitle: Demo\nbad key: x\n---\n"),
+            (3, 1, "invalid key 'bad key'".to_string())
+        );
+        assert_eq!(
+            parse_error("---\ntitle: Demo\n\nstatus: ready\ntitle: Again\n---\n"),
+            (5, 1, "duplicate key 'title'".to_string())
+        );
+        assert_eq!(
+            parse_error("---\n  - orphan\n---\n"),
+            (2, 1, "indented line before any key".to_string())
+        );
+    }
+
+    #[test]
+    fn value_errors_point_at_the_value() {
+        assert_eq!(
+            parse_error("---\ntitle: \"open\n---\n"),
+            (2, 8, "unterminated \" quote".to_string())
+        );
+        assert_eq!(
+            parse_error("---\ntitle: Demo\ncreated: soon\n---\n"),
+            (
+                3,
+                10,
+                "'created' must be a date like \"2025-01-31\"".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn duplicate_sections_are_rejected_whatever_their_case() {
+        let content = "---\ntitle: Demo\n---\n\n## Goals\n- One\n\n## goals\n- Two\n";
+        assert_eq!(
+            parse_error(content),
+            (8, 1, "duplicate '## goals' section".to_string())
+        );
+    }
+
+    #[test]
+    fn headings_inside_a_fence_are_not_sections() {
+        let content = "---\ntitle: Demo\n---\n\n## Goals\n```\n## Goals\n```\n";
+        let task = Task::parse(Path::new("TASK.md"), content).unwrap();
+        assert_eq!(task.sections.len(), 1);
+
+        assert_eq!(
+            parse_error("---\ntitle: Demo\n---\n\n## Goals\n```\ncode\n"),
+            (7, 1, "code fence is not closed".to_string())
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::{self, Config};
+use crate::files::{self, collect_files, relative, remove_empty_parents};
+use crate::manifest::{self, Manifest};
+use crate::profiles::{PlannedFile, ProfileRegistry};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Remove what `cdd install` created. Only files recorded in the manifest
+// and still unmodified are deleted; task files, config and edited files are
+// kept unless `purge` is set. Everything touched is first saved to a
+// timestamped tar backup in the project root.
+//
+// `shipped` lists the files the current binary would install, used in place
+// of a manifest for projects installed before manifests existed.
+pub fn uninstall(
+    registry: &ProfileRegistry,
+    config: &Config,
+    root: &Path,
+    shipped: &[(String, PlannedFile)],
+    purge: bool,
+) -> io::Result<()> {
+    println!("🗑️  Uninstalling CDD files...");
+
+    let context_path = root.join(".context");
+    let tasks_dir = config.tasks_dir(root);
+
+    let manifest = match Manifest::load(root)? {
+        Some(manifest) => manifest,
+        None => {
+            println!("  No install manifest found; only files matching the shipped versions are removed.");
+            let mut manifest = Manifest::default();
+            for (owner, file) in shipped {
+                manifest.record(root, &file.path, file.contents.as_bytes(), owner);
+            }
+            manifest
+        }
+    };
+
+    let mut to_remove: Vec<PathBuf> = Vec::new();
+    let mut kept: Vec<PathBuf> = Vec::new();
+
+    for entry in &manifest.files {
+        // Files in shared locations (e.g. ~/.codex/prompts) serve other projects too
+        if !entry.is_inside_project() {
+            continue;
+        }
+
+        let path = entry.absolute_path(root);
+        if !path.exists() {
+            continue;
+        }
+
+        if purge || manifest.is_unmodified(root, &path) {
+            to_remove.push(path);
+        } else {
+            kept.push(path);
+        }
+    }
+
+    // cdd's own bookkeeping files
+    for name in [".version", ".manifest.toml"] {
+        to_remove.push(context_path.join(name));
+    }
+    collect_files(&manifest::base_dir(root), &mut to_remove)?;
+    collect_files(&context_path.join(".snapshots"), &mut to_remove)?;
+
+    // --purge also takes task history, config and custom profiles
+    if purge {
+        collect_files(&context_path, &mut to_remove)?;
+        collect_files(&tasks_dir, &mut to_remove)?;
+    }
+
+    to_remove.retain(|path| path.is_file());
+    to_remove.sort();
+    to_remove.dedup();
+
+    // Shared files (AGENTS.md, .aider.conf.yml, ...) are edited rather than removed
+    let shared: Vec<PathBuf> = registry
+        .trusted()
+        .filter(|profile| profile.is_installed(root))
+        .flat_map(|profile| profile.shared_files(root))
+        .filter(|path| path.is_file())
+        .collect();
+
+    if to_remove.is_empty() && shared.is_empty() {
+        println!("  No CDD files found to remove.");
+        return Ok(());
+    }
+
+    let mut backed_up = to_remove.clone();
+    backed_up.extend(shared);
+    let backup_path = root.join(format!("cdd-backup-{}.tar", files::timestamp()));
+    files::write_tar(root, &backup_path, &backed_up)?;
+    println!("  📦 Backup written to {}", relative(root, &backup_path));
+
+    let mut removed_count = 0;
+
+    for path in &to_remove {
+        fs::remove_file(path)?;
+        println!("  ✓ Removed {}", relative(root, path));
+        removed_count += 1;
+    }
+
+    for profile in registry.trusted() {
+        for removed in profile.uninstall(root)? {
+            println!("  ✓ Removed {}", removed);
+            removed_count += 1;
+        }
+    }
+
+    if !purge {
+        config::forget_installed_agents(root)?;
+    }
+
+    // Clean up directories left empty (.claude/commands, .context/_reference/rules, ...)
+    for path in &to_remove {
+        remove_empty_parents(root, path);
+    }
+
+    for path in &kept {
+        println!("  • Kept {} (modified since install)", relative(root, path));
+    }
+    if !purge && tasks_dir.exists() {
+        println!(
+            "  • Kept {}/ (use --purge to remove task files)",
+            relative(root, &tasks_dir)
+        );
+    }
+
+    println!(
+        "\n✅ Uninstall complete! Removed {} item(s).",
+        removed_count
+    );
+    println!("Note: This only removed CDD files from the current directory.");
+    println!("To uninstall the cdd binary itself, run: cargo uninstall context-driven-development");
+
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use crate::manifest::{self, Manifest};
+use crate::merge;
+use crate::profiles::PlannedFile;
+use inquire::Select;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// --force / --dry-run
+#[derive(Clone, Copy, Default)]
+pub struct UpgradeOptions {
+    pub force: bool,
+    pub dry_run: bool,
+}
+
+// What installing a shipped file will do to the copy on disk
+pub enum Action {
+    Create,
+    Unchanged,
+    // Untouched since the last install, replaced with the new version
+    Update,
+    // Edited locally and changed upstream, merged without conflicts
+    Merge(String),
+    // Merged, with this many regions wrapped in conflict markers
+    Conflict(String, usize),
+    // Edited locally, nothing new upstream
+    KeepLocal,
+    // Edited locally but there is no shipped copy to merge against
+    NoBase,
+    // Edited locally and replaced because of --force
+    Overwrite,
+}
+
+pub struct Change {
+    pub file: PlannedFile,
+    pub action: Action,
+    // Manifest owner: "reference" or the profile id
+    pub owner: String,
+}
+
+const ACCEPT: &str = "Accept the new version";
+const KEEP: &str = "Keep my version";
+const MERGE: &str = "Merge (three-way, conflicts get markers)";
+
+// Decide what to do with each shipped file, comparing the copy on disk with
+// the manifest hash and the stored base version. `released` holds the copies
+// earlier releases shipped, oldest first, for installs from before the
+// manifest: a file matching one is untouched, and the last is the merge base.
+pub fn plan(
+    root: &Path,
+    manifest: &Manifest,
+    files: Vec<PlannedFile>,
+    released: &[PlannedFile],
+    owner: &str,
+    force: bool,
+    theirs_label: &str,
+) -> Vec<Change> {
+    files
+        .into_iter()
+        .map(|file| {
+            let action = classify(root, manifest, &file, released, force, theirs_label);
+            Change {
+                file,
+                action,
+                owner: owner.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn classify(
+    root: &Path,
+    manifest: &Manifest,
+    file: &PlannedFile,
+    released: &[PlannedFile],
+    force: bool,
+    theirs_label: &str,
+) -> Action {
+    let Ok(local) = fs::read_to_string(&file.path) else {
+        return Action::Create;
+    };
+
+    if local == file.contents {
+        return Action::Unchanged;
+    }
+    let earlier: Vec<&String> = released
+        .iter()
+        .filter(|earlier| earlier.path == file.path)
+        .map(|earlier| &earlier.contents)
+        .collect();
+    if manifest.is_unmodified(root, &file.path) || earlier.contains(&&local) {
+        return Action::Update;
+    }
+    if force {
+        return Action::Overwrite;
+    }
+
+    let Some(base) = manifest::read_base(root, &file.path)
+        .or_else(|| earlier.last().map(|contents| contents.to_string()))
+    else {
+        return Action::NoBase;
+    };
+    if base == file.contents {
+        return Action::KeepLocal;
+    }
+
+    let merged = merge::merge3(&base, &local, &file.contents, "local", theirs_label);
+    if merged.conflicts == 0 {
+        Action::Merge(merged.text)
+    } else {
+        Action::Conflict(merged.text, merged.conflicts)
+    }
+}
+
+// Write the planned changes and record the shipped versions as the new base
+pub fn apply(root: &Path, manifest: &mut Manifest, changes: &[Change]) -> io::Result<()> {
+    for change in changes {
+        let file = &change.file;
+        let contents = match &change.action {
+            Action::Create | Action::Update | Action::Overwrite => Some(&file.contents),
+            Action::Merge(text) | Action::Conflict(text, _) => Some(text),
+            Action::Unchanged | Action::KeepLocal => None,
+            // Without a base, recording one now would make a later merge
+            // treat stale content as local edits
+            Action::NoBase => continue,
+        };
+
+        if let Some(contents) = contents {
+            if let Some(parent) = file.path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&file.path, contents)?;
+        }
+
+        // The manifest holds the shipped hash, so merged files still count as modified
+        manifest.record(root, &file.path, file.contents.as_bytes(), &change.owner);
+        manifest::write_base(root, &file.path, &file.contents)?;
+    }
+
+    Ok(())
+}
+
+pub fn print_plan(root: &Path, changes: &[Change]) {
+    for change in changes {
+        let path = change
+            .file
+            .path
+            .strip_prefix(root)
+            .unwrap_or(&change.file.path);
+        let path = path.display();
+
+        match &change.action {
+            Action::Create => println!("  + {} (new)", path),
+            Action::Update => println!("  ~ {} (updated)", path),
+            Action::Merge(_) => println!("  ⇄ {} (merged with local changes)", path),
+            Action::Conflict(_, count) => println!(
+                "  ! {} ({} conflict(s), resolve the <<<<<<< markers)",
+                path, count
+            ),
+            Action::KeepLocal => println!("  • {} (kept local changes)", path),
+            Action::NoBase => println!(
+                "  • {} (kept: modified locally and no base version to merge with, use --force to overwrite)",
+                path
+            ),
+            Action::Overwrite => println!("  ~ {} (overwritten, local changes discarded)", path),
+            Action::Unchanged => {}
+        }
+    }
+}
+
+pub fn conflict_count(changes: &[Change]) -> usize {
+    changes
+        .iter()
+        .filter(|change| matches!(change.action, Action::Conflict(..)))
+        .count()
+}
+
+// Files with both local edits and a new shipped version
+pub fn needs_review(change: &Change) -> bool {
+    matches!(
+        change.action,
+        Action::Merge(_) | Action::Conflict(..) | Action::NoBase
+    )
+}
+
+// Show the diff for each file needing review and ask whether to accept the
+// shipped version, keep the local one or merge them
+pub fn review(root: &Path, changes: &mut [Change], theirs_label: &str) -> io::Result<()> {
+    for change in changes.iter_mut().filter(|change| needs_review(change)) {
+        let path = &change.file.path;
+        let display = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        let local = fs::read_to_string(path)?;
+
+        println!(
+            "\n{} has local changes. Differences from the new version:",
+            display
+        );
+        print!(
+            "{}",
+            merge::unified_diff(&local, &change.file.contents, "local", theirs_label)
+        );
+
+        let mut options = vec![ACCEPT, KEEP];
+        // Merging needs the previously shipped version as the common ancestor
+        if !matches!(change.action, Action::NoBase) {
+            options.push(MERGE);
+        }
+
+        let choice = Select::new(&format!("{}:", display), options)
+            .prompt()
+            .map_err(|e| io::Error::new(io::ErrorKind::Interrupted, e.to_string()))?;
+        match choice {
+            ACCEPT => change.action = Action::Overwrite,
+            KEEP => change.action = Action::KeepLocal,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// One-line count of what an update did
+pub fn print_summary(changes: &[Change]) {
+    let count = |predicate: fn(&Action) -> bool| {
+        changes
+            .iter()
+            .filter(|change| predicate(&change.action))
+            .count()
+    };
+
+    println!(
+        "{} new, {} updated, {} merged, {} with conflicts, {} kept with local changes, {} unchanged",
+        count(|action| matches!(action, Action::Create)),
+        count(|action| matches!(action, Action::Update | Action::Overwrite)),
+        count(|action| matches!(action, Action::Merge(_))),
+        count(|action| matches!(action, Action::Conflict(..))),
+        count(|action| matches!(action, Action::KeepLocal | Action::NoBase)),
+        count(|action| matches!(action, Action::Unchanged)),
+    );
+}